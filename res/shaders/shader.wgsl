@@ -17,7 +17,37 @@ struct RenderData {
     wind_direction: f32,
     wind_noise_scale: f32,
     wind_noise_strength: f32,
-    padding: f32
+    growth: f32,
+    emissive_intensity: f32,
+    tone_mapping: f32,
+    exposure: f32,
+    fog_density: f32,
+    fog_color: vec3<f32>,
+    padding: f32,
+    sky_horizon: vec3<f32>,
+    padding_1: f32,
+    sky_zenith: vec3<f32>,
+    padding_2: f32,
+    light_direction: vec3<f32>,
+    light_intensity: f32,
+    light_color: vec3<f32>,
+    light_enabled: f32,
+    ambient: f32,
+    ao_strength: f32,
+    ao_radius: f32,
+    dust_size: f32,
+    outline_color: vec3<f32>,
+    outline_width: f32,
+    gust_x: f32,
+    gust_z: f32,
+    wind_phase: f32,
+    padding_4: f32,
+    water_color: vec3<f32>,
+    water_ripple: f32,
+    wind_field_scale: f32,
+    wind_field_speed: f32,
+    wind_field_strength: f32,
+    padding_5: f32,
 };
 
 @group(1) @binding(0)
@@ -37,6 +67,13 @@ struct VertexInput{
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) age: f32,
+    @location(3) color: vec3<f32>,
+    @location(4) color_weight: f32,
+    // x is the lut, y is the base height
+    @location(13) lut_height: vec2<f32>,
+    // x is metallic, y is roughness
+    @location(14) material: vec2<f32>,
+    @location(15) uv: vec2<f32>,
 };
 
 struct InstanceInput{
@@ -57,6 +94,12 @@ struct VertexOutput{
     @location(1) age: f32,
     @location(2) world_pos: vec3<f32>,
     @location(3) scale: vec3<f32>,
+    @location(4) color: vec4<f32>,
+    @location(5) @interpolate(flat) lut: f32,
+    @location(6) base_height: f32,
+    @location(7) height: f32,
+    @location(8) material: vec2<f32>,
+    @location(9) uv: vec2<f32>,
 };
 
 @vertex
@@ -64,6 +107,36 @@ fn vs_main(
     model: VertexInput,
     instance: InstanceInput
 ) -> VertexOutput{
+    return transform(model, instance, 0.0);
+}
+
+// inverted hull, pushed away from the camera so the plant covers it everywhere except past its silhouette
+@vertex
+fn vs_outline(
+    model: VertexInput,
+    instance: InstanceInput
+) -> VertexOutput{
+    var out = transform(model, instance, render_data.outline_width);
+    let world_position = out.world_pos + normalize(camera.direction.xyz) * render_data.outline_width * 2.0;
+    out.clip_position = camera.view_proj * vec4<f32>(world_position, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_outline(in: VertexOutput) -> @location(0) vec4<f32>{
+    if in.age > render_data.growth {
+        discard;
+    }
+
+    return vec4<f32>(apply_fog(render_data.outline_color, in.world_pos), 1.0);
+}
+
+// inflate moves the vertex out along its normal, used by the outline
+fn transform(
+    model: VertexInput,
+    instance: InstanceInput,
+    inflate: f32,
+) -> VertexOutput{
 
     let model_matrix = mat4x4<f32>(
         instance.model_matrix_0,
@@ -81,16 +154,22 @@ fn vs_main(
 
     var world_position = model_matrix * vec4<f32>(model.position, 1.0);
     let normal = transpose(inv_model_matrix) * vec4<f32>(model.normal, 1.0);
+    world_position += vec4<f32>(normalize(normal.xyz) * inflate, 0.0);
 
     let scale1 = length(vec3<f32>(instance.model_matrix_0.x, instance.model_matrix_1.x, instance.model_matrix_2.x));
     let scale2 = length(vec3<f32>(instance.model_matrix_0.y, instance.model_matrix_1.y, instance.model_matrix_2.y));
     let scale3 = length(vec3<f32>(instance.model_matrix_0.z, instance.model_matrix_1.z, instance.model_matrix_2.z));
     let scale = vec3<f32>(scale1, scale2, scale3);
 
+    // height above the instance origin, the ground is no longer at y = 0
+    let height = world_position.y - instance.model_matrix_3.y;
+
     let wind = calculate_wind(world_position.xz);
-    let t = min(world_position.y / 0.1, 1.0);
+    let t = min(height / 0.1, 1.0);
     let age = pow(min(model.age, 1.0), 2.0);
     world_position.z += wind * age * t;
+    world_position.x += render_data.gust_x * age * t;
+    world_position.z += render_data.gust_z * age * t;
     
     var out: VertexOutput;
     out.clip_position = camera.view_proj * world_position;
@@ -98,42 +177,124 @@ fn vs_main(
     out.age = model.age;
     out.world_pos = world_position.xyz;
     out.scale = scale;
+    out.color = vec4<f32>(model.color, model.color_weight);
+    out.lut = model.lut_height.x;
+    out.base_height = model.lut_height.y;
+    out.height = height;
+    out.material = model.material;
+    out.uv = model.uv;
     
     return out;
 }
 
-fn sample_ground(world_pos: vec3<f32>) -> vec3<f32> {
+fn lut_uv(t: f32) -> vec2<f32> {
+    return lut_row_uv(t, 0.0);
+}
+
+fn lut_row_uv(t: f32, row: f32) -> vec2<f32> {
+    let size = vec2<f32>(textureDimensions(lut_tex));
+    let v = (clamp(row, 0.0, size.y - 1.0) + 0.5) / size.y;
+    return vec2<f32>((clamp(t, 0.0, 1.0) * (size.x - 1.0) + 0.5) / size.x, v);
+}
+
+fn sample_ground(world_pos: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
     let uv = world_pos * 0.1;
     let ground = textureSample(tex, tex_sampler, uv.xz).r * 0.01;
-    let lut = textureSample(lut_tex, lut_tex_sampler, vec2<f32>(0.0, 0.5)).rgb;
-    return lut * (1.0 - ground);
+    let lut = textureSample(lut_tex, lut_tex_sampler, lut_uv(0.0)).rgb;
+    return lut * (1.0 - ground) * get_light(normal);
 }
 
-fn get_shadow(normal: vec3<f32>) -> f32{
-    let light_dir = vec3<f32>(-1.0);
+// fitted aces curve, only applied when tone mapping is enabled so the default output stays untouched
+fn tone_map(color: vec3<f32>) -> vec3<f32> {
+    if render_data.tone_mapping < 0.5 {
+        return color;
+    }
+
+    let x = color * render_data.exposure;
+    let mapped = (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+    return clamp(mapped, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn apply_fog(color: vec3<f32>, world_pos: vec3<f32>) -> vec3<f32> {
+    let depth = max(dot(world_pos - camera.position.xyz, camera.direction.xyz), 0.0);
+    let fog = 1.0 - exp(-depth * render_data.fog_density);
+    return mix(color, render_data.fog_color, fog);
+}
 
-    let d = max(dot(light_dir, normal), 0.0);
+fn get_shadow(normal: vec3<f32>) -> f32{
+    let d = max(dot(render_data.light_direction, normal), 0.0);
     return 1.0 - d * 0.05;
 }
 
+// lambert with an ambient floor, falls back to the old flat shading when the light is off
+fn get_light(normal: vec3<f32>) -> vec3<f32> {
+    if render_data.light_enabled < 0.5 {
+        return vec3<f32>(get_shadow(normal) * render_data.light_intensity);
+    }
+
+    let d = max(dot(-normalize(render_data.light_direction), normalize(normal)), 0.0);
+    return vec3<f32>(render_data.ambient) + render_data.light_color * render_data.light_intensity * d;
+}
+
+// blinn-phong with a schlick fresnel, fully rough surfaces get no highlight at all
+fn get_specular(normal: vec3<f32>, albedo: vec3<f32>, material: vec2<f32>) -> vec3<f32> {
+    let metallic = material.x;
+    let roughness = material.y;
+    if roughness >= 1.0 {
+        return vec3<f32>(0.0);
+    }
+
+    var light_color = vec3<f32>(render_data.light_intensity);
+    if render_data.light_enabled > 0.5 {
+        light_color = render_data.light_color * render_data.light_intensity;
+    }
+
+    let n = normalize(normal);
+    let l = -normalize(render_data.light_direction);
+    let v = -normalize(camera.direction.xyz);
+    let h = normalize(l + v);
+
+    let shininess = mix(256.0, 2.0, roughness);
+    let highlight = pow(max(dot(n, h), 0.0), shininess) * max(dot(n, l), 0.0);
+    let f0 = mix(vec3<f32>(0.04), albedo, metallic);
+    let fresnel = f0 + (1.0 - f0) * pow(1.0 - max(dot(h, v), 0.0), 5.0);
+
+    return fresnel * highlight * light_color * (1.0 - roughness);
+}
+
 @fragment
 fn fs_color_object(in: VertexOutput) -> @location(0) vec4<f32>{
-    var ground = sample_ground(in.world_pos);    
-    var t = clamp(in.world_pos.y / 0.1, 0.0, 1.0);
+    if in.age > render_data.growth {
+        discard;
+    }
 
-    let lut = textureSample(lut_tex, lut_tex_sampler, vec2<f32>(in.age, 0.5)).rgb;
+    var ground = sample_ground(in.world_pos, vec3<f32>(0.0, 1.0, 0.0));    
+    var t = clamp(in.height / 0.1, 0.0, 1.0);
 
-    let color = lut * t * get_shadow(in.normal) + vec3<f32>(ground * (1.0 - t));
+    let lut = mix(textureSample(lut_tex, lut_tex_sampler, lut_row_uv(in.age, in.lut)).rgb, in.color.rgb, in.color.a);
 
-    return vec4<f32>(color, 1.0);
+    let ao = 1.0 - render_data.ao_strength * (1.0 - smoothstep(0.0, render_data.ao_radius, in.base_height));
+    // metals have no diffuse, their color comes through the tinted highlight instead
+    let diffuse = lut * (1.0 - in.material.x) * get_light(in.normal) * ao;
+    let specular = get_specular(in.normal, lut, in.material);
+    let color = (diffuse + specular) * t + vec3<f32>(ground * (1.0 - t));
+    let emission = lut * smoothstep(0.9, 1.0, in.age) * render_data.emissive_intensity;
+
+    return vec4<f32>(apply_fog(tone_map(color + emission), in.world_pos), 1.0);
 }
 
 @fragment
 fn fs_dust(in: VertexOutput) -> @location(0) vec4<f32>{
-    let t = 1.0 - in.scale.x / 0.01;
-    let color = textureSample(lut_tex, lut_tex_sampler, vec2<f32>(t, 0.5)).rgb;
+    let t = 1.0 - in.scale.x / (render_data.dust_size / 0.85);
+    let color = mix(textureSample(lut_tex, lut_tex_sampler, lut_uv(t)).rgb, in.color.rgb, in.color.a);
     // return vec4<f32>(vec3<f32>(in.scale.x/ 0.0085), 1.0);
-    return vec4<f32>(color, 1.0);
+    return vec4<f32>(apply_fog(tone_map(color), in.world_pos), 1.0);
+}
+
+@fragment
+fn fs_leaf(in: VertexOutput) -> @location(0) vec4<f32>{
+    let color = textureSample(lut_tex, lut_tex_sampler, lut_uv(in.age)).rgb * get_light(in.normal);
+    return vec4<f32>(apply_fog(tone_map(color), in.world_pos), 1.0);
 }
 
 @fragment
@@ -141,26 +302,69 @@ fn fs_floor(in: VertexOutput) -> @location(0) vec4<f32>{
     // let wind = calculate_wind(in.world_pos.xz);
     // return vec4<f32>(vec3<f32>(wind), 1.0);
 
-    return vec4<f32>(sample_ground(in.world_pos), 1.0);
+    return vec4<f32>(apply_fog(tone_map(sample_ground(in.world_pos, in.normal)), in.world_pos), 1.0);
+}
+
+// there's no mirrored pass, so only the sky gradient is reflected, blended in by a fresnel term
+@fragment
+fn fs_water(in: VertexOutput) -> @location(0) vec4<f32>{
+    let time = (render_data.time + render_data.wind_phase) * render_data.wind_speed;
+    let uv = in.world_pos.xz * render_data.wind_noise_scale;
+    let ripple_x = textureSample(tex, tex_sampler, uv + time * 0.01).r - 0.5;
+    let ripple_z = textureSample(tex, tex_sampler, uv.yx - time * 0.01).r - 0.5;
+    let normal = normalize(vec3<f32>(ripple_x * render_data.water_ripple, 1.0, ripple_z * render_data.water_ripple));
+
+    let view = normalize(camera.direction.xyz);
+    let reflected = reflect(view, normal);
+    let sky = mix(render_data.sky_horizon, render_data.sky_zenith, smoothstep(0.0, 1.0, reflected.y));
+    let fresnel = 0.02 + 0.98 * pow(1.0 - max(dot(normal, -view), 0.0), 5.0);
+    let color = mix(render_data.water_color * get_light(normal), sky, fresnel);
+
+    return vec4<f32>(apply_fog(tone_map(color), in.world_pos), 1.0);
 }
 
 @fragment
 fn fs_grass(in: VertexOutput) -> @location(0) vec4<f32>{
-    let ground = sample_ground(in.world_pos);
+    let ground = sample_ground(in.world_pos, vec3<f32>(0.0, 1.0, 0.0));
     
-    var t = min(in.world_pos.y / 0.1, 1.0);
-    let color = textureSample(lut_tex, lut_tex_sampler, vec2<f32>(t, 0.5)).rgb;
+    var t = min(in.height / 0.1, 1.0);
+    let color = textureSample(lut_tex, lut_tex_sampler, lut_uv(t)).rgb * get_light(vec3<f32>(0.0, 1.0, 0.0));
 
     // return vec4<f32>(vec3<f32>(t), 1.0);
+    return vec4<f32>(apply_fog(tone_map(color), in.world_pos), 1.0);
+}
+
+struct SkyOutput{
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// drawn last just in front of the far plane, so it only fills pixels nothing else covered
+@vertex
+fn vs_sky(model: VertexInput) -> SkyOutput {
+    var out: SkyOutput;
+    out.clip_position = vec4<f32>(model.position.xy, 0.99999, 1.0);
+    out.uv = model.position.xy * 0.5 + 0.5;
+    return out;
+}
+
+@fragment
+fn fs_sky(in: SkyOutput) -> @location(0) vec4<f32>{
+    let color = mix(render_data.sky_horizon, render_data.sky_zenith, smoothstep(0.0, 1.0, in.uv.y));
     return vec4<f32>(color, 1.0);
 }
 
 fn calculate_wind(coords: vec2<f32>) -> f32{
-    let time = render_data.time * render_data.wind_speed;
+    let time = (render_data.time + render_data.wind_phase) * render_data.wind_speed;
 
     let noise = textureSampleLevel(tex, tex_sampler, coords * render_data.wind_noise_scale + time * 0.01, 0.0).r;
 
+    // a wide noise layer scrolling along the wind, so stronger and weaker patches roll over the field
+    let field_uv = coords * render_data.wind_field_scale - vec2<f32>(0.0, render_data.time + render_data.wind_phase) * render_data.wind_field_speed;
+    let field = textureSampleLevel(tex, tex_sampler, field_uv, 0.0).r;
+    let strength = render_data.wind_strength * max(1.0 + (field - 0.5) * 2.0 * render_data.wind_field_strength, 0.0);
+
     var pos = (coords.x + coords.y) + noise * render_data.wind_noise_strength;
-    return sin(pos * render_data.wind_scale + time) * render_data.wind_strength;
+    return sin(pos * render_data.wind_scale + time) * strength;
 }
 
@@ -0,0 +1,676 @@
+use serde::Deserialize;
+
+const MAX_GRASS: u32 = 100_000;
+const MAX_GRID_COUNT: u32 = 32;
+const MAX_FLOOR_RESOLUTION: u32 = 512;
+const MAX_FLOOR_OCTAVES: u32 = 6;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GrassConfig {
+    pub count: u32,
+    pub range: f32,
+    pub iterations: u32,
+    pub height: f32,
+    pub width: f32,
+    // 0 keeps the flat quad as the cheap option
+    pub blade_segments: u32,
+}
+
+impl Default for GrassConfig {
+    fn default() -> Self {
+        Self {
+            count: 5000,
+            range: 2.75,
+            iterations: 12,
+            height: 0.1,
+            width: 0.0075,
+            blade_segments: 3,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GridConfig {
+    pub count: u32,
+    pub spacing: f32,
+    // every cell's plant is derived from this, none picks one at startup
+    pub seed: Option<u64>,
+    // 0 grows the same plant in every cell, 1 gives every cell its own
+    pub variation: f32,
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            count: 4,
+            spacing: 3.0,
+            seed: None,
+            variation: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct QualityConfig {
+    pub enabled: bool,
+    pub frame_budget_ms: f32,
+    // frames have to stay over / under the budget this long before the level changes
+    pub degrade_after_secs: f32,
+    pub recover_after_secs: f32,
+    // cap on grass, dust, leaf and plant instances together, 0 leaves them uncapped
+    pub instance_budget: u32,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frame_budget_ms: 1000.0 / 30.0,
+            degrade_after_secs: 1.0,
+            recover_after_secs: 3.0,
+            instance_budget: 0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LodConfig {
+    // off keeps every plant at full detail and lets new ones pop in
+    pub enabled: bool,
+    // world distance from the camera's ground point where plants drop to reduced detail
+    pub distance: f32,
+    // how far past the distance a plant has to get before it switches, either way
+    pub hysteresis: f32,
+    // reduced plants lose everything grown later than this, 0..1 over the iterations
+    pub prune_age: f32,
+    pub fade_secs: f32,
+}
+
+impl Default for LodConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            distance: 6.0,
+            hysteresis: 1.0,
+            prune_age: 0.7,
+            fade_secs: 0.8,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DustConfig {
+    pub size: f32,
+    pub lifetime_secs: f32,
+    pub color: Option<[f32; 3]>,
+}
+
+impl Default for DustConfig {
+    fn default() -> Self {
+        Self {
+            size: 0.0085,
+            lifetime_secs: 5.0,
+            color: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct FloorConfig {
+    // how far the heightmap lifts the ground, 0 gives the old flat floor
+    pub height: f32,
+    pub resolution: u32,
+    // side length in world units, the floor follows the camera so this only has to cover the view
+    pub size: f32,
+    // fbm layers of the heightmap, 1 is the plain noise texture
+    pub octaves: u32,
+    // how much each octave keeps of the previous one's amplitude
+    pub persistence: f32,
+}
+
+impl Default for FloorConfig {
+    fn default() -> Self {
+        Self {
+            height: 0.2,
+            resolution: 256,
+            size: 100.0,
+            octaves: 1,
+            persistence: 0.5,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct WaterConfig {
+    pub enabled: bool,
+    // world height of the surface, anything of the floor below it is under water
+    pub height: f32,
+    pub color: [f32; 3],
+    // how far the wind noise tilts the surface normal
+    pub ripple: f32,
+}
+
+impl Default for WaterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            height: 0.05,
+            color: [0.1, 0.3, 0.35],
+            ripple: 0.3,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LeavesConfig {
+    pub count: u32,
+    pub size: f32,
+}
+
+impl Default for LeavesConfig {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            size: 0.012,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RecordingConfig {
+    pub frames: u32,
+    pub prefix: String,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            frames: 60,
+            prefix: "frame".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GlowConfig {
+    pub enabled: bool,
+    pub intensity: f32,
+}
+
+impl Default for GlowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            intensity: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct OutlineConfig {
+    pub enabled: bool,
+    // in world units
+    pub width: f32,
+    pub color: [f32; 3],
+}
+
+impl Default for OutlineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            width: 0.004,
+            color: [0.05, 0.05, 0.05],
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ToneMappingConfig {
+    pub enabled: bool,
+    pub exposure: f32,
+}
+
+impl Default for ToneMappingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            exposure: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FogConfig {
+    pub density: f32,
+    pub color: [f32; 3],
+}
+
+impl Default for FogConfig {
+    fn default() -> Self {
+        Self {
+            density: 0.0,
+            color: [0.2, 0.5, 1.0],
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SkyConfig {
+    pub horizon: [f32; 3],
+    pub zenith: [f32; 3],
+}
+
+impl Default for SkyConfig {
+    fn default() -> Self {
+        Self {
+            horizon: [0.55, 0.75, 1.0],
+            zenith: [0.2, 0.5, 1.0],
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LightConfig {
+    pub enabled: bool,
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    pub ambient: f32,
+}
+
+impl Default for LightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            direction: [-1.0, -1.0, -1.0],
+            color: [1.0, 1.0, 1.0],
+            intensity: 0.6,
+            ambient: 0.5,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AoConfig {
+    pub enabled: bool,
+    pub strength: f32,
+    pub radius: f32,
+}
+
+impl Default for AoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: 0.4,
+            radius: 0.05,
+        }
+    }
+}
+
+// grass and dust get their own copy of the render data with these applied to the plant wind
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GrassWindConfig {
+    pub strength: f32,
+    pub scale: f32,
+    pub speed: f32,
+}
+
+impl Default for GrassWindConfig {
+    fn default() -> Self {
+        Self {
+            strength: 1.0,
+            scale: 1.0,
+            speed: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct WindFieldConfig {
+    // off keeps the wind uniform over the whole scene
+    pub enabled: bool,
+    // noise repeats per world unit, smaller means wider patches
+    pub scale: f32,
+    // how fast the patches travel along the wind, in noise tiles per second
+    pub speed: f32,
+    // 0 is uniform, 1 lets a patch range from still to double the wind
+    pub strength: f32,
+}
+
+impl Default for WindFieldConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scale: 0.05,
+            speed: 0.02,
+            strength: 0.8,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CursorWindConfig {
+    pub enabled: bool,
+    // how far a full gust pushes the tips, in world units
+    pub strength: f32,
+    // how fast the gust follows the cursor and fades once it stops, per second
+    pub decay: f32,
+}
+
+impl Default for CursorWindConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: 0.03,
+            decay: 2.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DayNightConfig {
+    pub enabled: bool,
+    pub speed: f32,
+    pub offset_hours: f32,
+}
+
+impl Default for DayNightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            speed: 1.0,
+            offset_hours: 0.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SeasonsConfig {
+    pub enabled: bool,
+    // one full cycle through every lut
+    pub period_secs: f32,
+    // names of luts from the l-system rendering config, in order
+    pub luts: Vec<String>,
+}
+
+impl Default for SeasonsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            period_secs: 1200.0,
+            luts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GrowthCurve {
+    // fast at first and settling in, what the plants have always done
+    #[default]
+    Ease,
+    Linear,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GrowthConfig {
+    // how far grown the plants are when the scene loads, 0..1 over the iterations,
+    // 1 shows them fully grown right away
+    pub start: f32,
+    // seconds until they're (nearly) fully grown
+    pub duration: f32,
+    pub curve: GrowthCurve,
+}
+
+impl Default for GrowthConfig {
+    fn default() -> Self {
+        Self {
+            start: 1.0,
+            duration: 2.0,
+            curve: GrowthCurve::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CameraConfig {
+    pub position: [f32; 3],
+    pub direction: [f32; 3],
+    pub ortho_width: f32,
+    pub pan_speed: f32,
+    // screen diagonal in pixels at which panning moves exactly pan_speed per pixel
+    pub reference_diagonal: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            position: [-9.5, 10.0, -9.5],
+            direction: normalize([1.0, -1.0, 1.0]).unwrap(),
+            ortho_width: 2.0,
+            pan_speed: 0.002,
+            reference_diagonal: 2202.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SceneConfig {
+    pub grass: GrassConfig,
+    pub grid: GridConfig,
+    pub floor: FloorConfig,
+    pub water: WaterConfig,
+    pub quality: QualityConfig,
+    pub lod: LodConfig,
+    pub leaves: LeavesConfig,
+    pub dust: DustConfig,
+    pub recording: RecordingConfig,
+    pub glow: GlowConfig,
+    pub outline: OutlineConfig,
+    pub tone_mapping: ToneMappingConfig,
+    pub fog: FogConfig,
+    pub sky: SkyConfig,
+    pub day_night: DayNightConfig,
+    pub cursor_wind: CursorWindConfig,
+    pub grass_wind: GrassWindConfig,
+    pub wind_field: WindFieldConfig,
+    pub seasons: SeasonsConfig,
+    pub growth: GrowthConfig,
+    pub light: LightConfig,
+    pub ao: AoConfig,
+    pub camera: CameraConfig,
+    pub lut_resolution: u32,
+    pub max_fps: u32,
+    pub weld: bool,
+    // none keeps the wind starting at phase 0, a seed shifts it the same way on every run
+    pub wind_seed: Option<u64>,
+}
+
+impl Default for SceneConfig {
+    fn default() -> Self {
+        Self {
+            grass: Default::default(),
+            grid: Default::default(),
+            floor: Default::default(),
+            water: Default::default(),
+            quality: Default::default(),
+            lod: Default::default(),
+            leaves: Default::default(),
+            dust: Default::default(),
+            recording: Default::default(),
+            glow: Default::default(),
+            outline: Default::default(),
+            tone_mapping: Default::default(),
+            fog: Default::default(),
+            sky: Default::default(),
+            day_night: Default::default(),
+            cursor_wind: Default::default(),
+            grass_wind: Default::default(),
+            wind_field: Default::default(),
+            seasons: Default::default(),
+            growth: Default::default(),
+            light: Default::default(),
+            ao: Default::default(),
+            camera: Default::default(),
+            lut_resolution: 256,
+            max_fps: 0,
+            weld: false,
+            wind_seed: None,
+        }
+    }
+}
+
+impl SceneConfig {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct SceneConfigJSON {
+            #[serde(default)]
+            scene: SceneConfig,
+        }
+
+        let mut scene = json5::from_str::<SceneConfigJSON>(json)
+            .map_err(|err| err.to_string())?
+            .scene;
+
+        if scene.grass.count > MAX_GRASS {
+            log::warn!(
+                "grass count {} is over the limit, clamping to {MAX_GRASS}",
+                scene.grass.count
+            );
+            scene.grass.count = MAX_GRASS;
+        }
+
+        if scene.grid.count > MAX_GRID_COUNT {
+            log::warn!(
+                "grid count {} is over the limit, clamping to {MAX_GRID_COUNT}",
+                scene.grid.count
+            );
+            scene.grid.count = MAX_GRID_COUNT;
+        }
+
+        if scene.floor.resolution > MAX_FLOOR_RESOLUTION {
+            log::warn!(
+                "floor resolution {} is over the limit, clamping to {MAX_FLOOR_RESOLUTION}",
+                scene.floor.resolution
+            );
+            scene.floor.resolution = MAX_FLOOR_RESOLUTION;
+        }
+
+        if scene.quality.degrade_after_secs < 0.0 || scene.quality.recover_after_secs < 0.0 {
+            log::warn!("quality windows can't be negative, using the defaults");
+            let default = QualityConfig::default();
+            scene.quality.degrade_after_secs = default.degrade_after_secs;
+            scene.quality.recover_after_secs = default.recover_after_secs;
+        }
+
+        let plants = scene.grid.count * scene.grid.count;
+        if scene.quality.instance_budget > 0 && scene.quality.instance_budget < plants {
+            log::warn!(
+                "instance budget {} doesn't cover the {plants} plants, grass and dust are left out",
+                scene.quality.instance_budget
+            );
+        }
+
+        if scene.floor.octaves > MAX_FLOOR_OCTAVES {
+            log::warn!(
+                "floor octaves {} is over the limit, clamping to {MAX_FLOOR_OCTAVES}",
+                scene.floor.octaves
+            );
+            scene.floor.octaves = MAX_FLOOR_OCTAVES;
+        }
+
+        if scene.floor.size <= 0.0 {
+            log::warn!("floor size has to be positive, using the default");
+            scene.floor.size = FloorConfig::default().size;
+        }
+
+        if !(0.0..=1.0).contains(&scene.growth.start) {
+            log::warn!("growth start has to be between 0 and 1, clamping");
+            scene.growth.start = scene.growth.start.clamp(0.0, 1.0);
+        }
+
+        if scene.growth.duration <= 0.0 {
+            log::warn!("growth duration has to be positive, using the default");
+            scene.growth.duration = GrowthConfig::default().duration;
+        }
+
+        if !(0.0..=1.0).contains(&scene.grid.variation) {
+            log::warn!("grid variation has to be between 0 and 1, clamping");
+            scene.grid.variation = scene.grid.variation.clamp(0.0, 1.0);
+        }
+
+        if scene.lod.hysteresis < 0.0 {
+            log::warn!("lod hysteresis can't be negative, using the default");
+            scene.lod.hysteresis = LodConfig::default().hysteresis;
+        }
+
+        if scene.lod.fade_secs <= 0.0 {
+            log::warn!("lod fade has to take some time, using the default");
+            scene.lod.fade_secs = LodConfig::default().fade_secs;
+        }
+
+        if scene.grid.spacing <= 0.0 {
+            log::warn!("grid spacing has to be positive, using the default");
+            scene.grid.spacing = GridConfig::default().spacing;
+        }
+
+        // the follow camera keeps its height over the ground along this, a level or upward
+        // direction never reaches the ground
+        match normalize(scene.camera.direction) {
+            Some(direction) if direction[1] < 0.0 => scene.camera.direction = direction,
+            Some(_) => {
+                log::warn!("camera direction has to point down, using the default");
+                scene.camera.direction = CameraConfig::default().direction;
+            }
+            None => {
+                log::warn!("camera direction can't be zero, using the default");
+                scene.camera.direction = CameraConfig::default().direction;
+            }
+        }
+
+        if scene.camera.reference_diagonal <= 0.0 {
+            log::warn!("camera reference diagonal has to be positive, using the default");
+            scene.camera.reference_diagonal = CameraConfig::default().reference_diagonal;
+        }
+
+        if normalize(scene.light.direction).is_none() {
+            log::warn!("light direction can't be zero, using the default");
+            scene.light.direction = LightConfig::default().direction;
+        }
+
+        Ok(scene)
+    }
+}
+
+fn normalize([x, y, z]: [f32; 3]) -> Option<[f32; 3]> {
+    let length = (x * x + y * y + z * z).sqrt();
+    if length > f32::EPSILON {
+        Some([x / length, y / length, z / length])
+    } else {
+        None
+    }
+}
@@ -0,0 +1,87 @@
+use std::cell::Cell;
+
+use jandering_engine::{
+    core::{engine::Events, window::WindowEvent},
+    types::{Vec2, Vec3},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::config::CursorWindConfig;
+
+// screen diagonals per second that count as a full gust
+const FULL_GUST_SPEED: f32 = 1.0;
+// long enough that different seeds land on unrelated parts of the wind cycle
+const MAX_WIND_PHASE: f32 = 1000.0;
+
+#[derive(Default)]
+pub struct CursorWind {
+    last_position: Option<Vec2>,
+    // pixels moved since the last real frame, skipped frames keep adding to it
+    motion: Vec2,
+    velocity: Vec2,
+    pub gust: Vec3,
+}
+
+impl CursorWind {
+    pub fn track(&mut self, events: &Events) {
+        // moves add up to the difference between the first and last position, so only the
+        // latest one matters
+        let latest = Cell::new(None);
+        let left = Cell::new(false);
+        events.matches(|event| {
+            match event {
+                WindowEvent::MouseMotion(position) => latest.set(Some(Vec2::from(*position))),
+                WindowEvent::MouseLeft => left.set(true),
+                _ => {}
+            }
+            false
+        });
+
+        if let Some(position) = latest.get() {
+            if let Some(last) = self.last_position {
+                self.motion += position - last;
+            }
+            self.last_position = Some(position);
+        }
+        if left.get() {
+            self.last_position = None;
+        }
+    }
+
+    // eases towards the latest cursor velocity, with the cursor still that's zero so the gust
+    // fades out and only the ambient wind is left
+    pub fn update(
+        &mut self,
+        config: &CursorWindConfig,
+        dt: f32,
+        diagonal: f32,
+        camera_direction: Vec3,
+    ) {
+        if dt <= 0.0 {
+            return;
+        }
+
+        let target = self.motion / (diagonal.max(1.0) * dt);
+        self.motion = Vec2::ZERO;
+        self.velocity += (target - self.velocity) * (1.0 - (-config.decay * dt).exp());
+
+        if !config.enabled {
+            self.gust = Vec3::ZERO;
+            return;
+        }
+
+        // screen right and screen down laid flat on the ground
+        let forward = Vec3::new(camera_direction.x, 0.0, camera_direction.z).normalize_or_zero();
+        let right = forward.cross(Vec3::Y);
+        let speed = self.velocity.length();
+        let direction = (right * self.velocity.x - forward * self.velocity.y).normalize_or_zero();
+        self.gust = direction * (speed / FULL_GUST_SPEED).min(1.0) * config.strength;
+    }
+}
+
+// anything random in the wind goes through the seed, so the same seed and time give the same wind
+pub fn wind_phase(seed: Option<u64>) -> f32 {
+    seed.map_or(0.0, |seed| {
+        StdRng::seed_from_u64(seed).gen_range(0.0..MAX_WIND_PHASE)
+    })
+}
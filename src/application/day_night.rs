@@ -0,0 +1,65 @@
+use std::f32::consts::TAU;
+
+use jandering_engine::types::Vec3;
+
+use super::config::{DayNightConfig, SkyConfig};
+
+const NIGHT_HORIZON: Vec3 = Vec3::new(0.06, 0.08, 0.18);
+const NIGHT_ZENITH: Vec3 = Vec3::new(0.01, 0.02, 0.06);
+const DUSK: Vec3 = Vec3::new(1.0, 0.55, 0.3);
+const NIGHT_INTENSITY: f32 = 0.35;
+
+pub struct Sun {
+    pub direction: Vec3,
+    pub intensity: f32,
+    pub horizon: Vec3,
+    pub zenith: Vec3,
+}
+
+impl Sun {
+    pub fn new(config: &DayNightConfig, sky: &SkyConfig) -> Self {
+        let day_horizon = Vec3::from(sky.horizon);
+        let day_zenith = Vec3::from(sky.zenith);
+        if !config.enabled {
+            return Self {
+                direction: Vec3::splat(-1.0),
+                intensity: 1.0,
+                horizon: day_horizon,
+                zenith: day_zenith,
+            };
+        }
+
+        // 0 at 6:00, so the sun rises on the horizon and peaks at noon
+        let angle = (hour_of_day(config) - 6.0) / 24.0 * TAU;
+        let elevation = angle.sin();
+        let daylight = smoothstep(-0.1, 0.3, elevation);
+        let dusk = (1.0 - (elevation / 0.3).abs()).max(0.0);
+
+        let horizon = NIGHT_HORIZON
+            .lerp(day_horizon, daylight)
+            .lerp(DUSK, dusk * 0.6);
+        let zenith = NIGHT_ZENITH.lerp(day_zenith, daylight);
+        let direction =
+            -Vec3::new(angle.cos(), elevation.max(0.2), 0.5).normalize() * Vec3::ONE.length();
+
+        Self {
+            direction,
+            intensity: NIGHT_INTENSITY + (1.0 - NIGHT_INTENSITY) * daylight,
+            horizon,
+            zenith,
+        }
+    }
+}
+
+// counted from the unix epoch in local time so it stays continuous over midnight at any speed
+fn hour_of_day(config: &DayNightConfig) -> f32 {
+    let now = js_sys::Date::new_0();
+    let local_ms = now.get_time() - now.get_timezone_offset() * 60_000.0;
+    let hours = local_ms / 3_600_000.0 * config.speed as f64 + config.offset_hours as f64;
+    hours.rem_euclid(24.0) as f32
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,121 @@
+use jandering_engine::{
+    core::{
+        engine::Events,
+        object::Instance,
+        renderer::{get_typed_bind_group, Renderer},
+        window::Key,
+    },
+    types::{Mat4, Vec3},
+};
+use rand::Rng;
+
+use crate::color_obj::AgeObject;
+
+use super::{logic::camera_ground_intersection, Application};
+
+const GALLERY_SIZE: i32 = 3;
+const GALLERY_SPACING: f32 = 1.5;
+const SELECTED_SCALE: f32 = 1.2;
+
+pub struct GalleryPlant {
+    pub seed: u64,
+    pub object: AgeObject,
+    scale: f32,
+}
+
+pub struct Gallery {
+    pub plants: Vec<GalleryPlant>,
+    pub selected: usize,
+    center: Vec3,
+}
+
+impl Application {
+    pub fn toggle_gallery(&mut self, renderer: &mut dyn Renderer) {
+        if let Some(gallery) = self.gallery.take() {
+            let seed = gallery.plants[gallery.selected].seed;
+            log::info!("picked plant with seed {seed}");
+            self.picked_seed = Some(seed);
+            self.plants.clear();
+            return;
+        }
+
+        let camera = get_typed_bind_group(renderer, self.camera).unwrap();
+        let center = camera_ground_intersection(camera.direction(), camera.position())
+            .unwrap_or(Vec3::ZERO);
+
+        let mut gallery = Gallery {
+            plants: Vec::new(),
+            selected: 0,
+            center,
+        };
+        self.fill_gallery(&mut gallery, renderer);
+        self.gallery = Some(gallery);
+    }
+
+    pub fn update_gallery(&mut self, dt: f32, events: &Events, renderer: &mut dyn Renderer) {
+        let Some(mut gallery) = self.gallery.take() else {
+            return;
+        };
+
+        let n = gallery.plants.len();
+        if events.is_pressed(Key::E) {
+            gallery.selected = (gallery.selected + 1) % n;
+            log::info!("selected seed {}", gallery.plants[gallery.selected].seed);
+        }
+        if events.is_pressed(Key::Q) {
+            gallery.selected = (gallery.selected + n - 1) % n;
+            log::info!("selected seed {}", gallery.plants[gallery.selected].seed);
+        }
+        if events.is_pressed(Key::R) {
+            self.fill_gallery(&mut gallery, renderer);
+        }
+
+        let t = 1.0 - (-15.0 * dt).exp();
+        for (i, plant) in gallery.plants.iter_mut().enumerate() {
+            let target = if i == gallery.selected {
+                SELECTED_SCALE
+            } else {
+                1.0
+            };
+            plant.scale += (target - plant.scale) * t;
+
+            let instance = plant.object.instances.first_mut().unwrap();
+            let (_, rotation, pos) = instance.mat().to_scale_rotation_translation();
+            instance.set_mat(Mat4::from_scale_rotation_translation(
+                Vec3::splat(plant.scale),
+                rotation,
+                pos,
+            ));
+            plant.object.update(renderer);
+        }
+
+        self.gallery = Some(gallery);
+    }
+
+    fn fill_gallery(&mut self, gallery: &mut Gallery, renderer: &mut dyn Renderer) {
+        let half = GALLERY_SIZE / 2;
+        gallery.plants.clear();
+        gallery.selected = 0;
+
+        for z in -half..=half {
+            for x in -half..=half {
+                let seed = self.rng.gen();
+                let (vertices, indices, _) = self.new_plant(seed);
+                let mut pos = gallery.center
+                    + Vec3::new(x as f32 * GALLERY_SPACING, 0.0, z as f32 * GALLERY_SPACING);
+                pos.y = self.ground_height(pos.x, pos.z);
+                let object = AgeObject::new(
+                    renderer,
+                    vertices,
+                    indices,
+                    vec![Instance::default().translate(pos)],
+                );
+                gallery.plants.push(GalleryPlant {
+                    seed,
+                    object,
+                    scale: 1.0,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use jandering_engine::{
+    core::{object::Instance, renderer::Renderer},
+    types::{Mat4, Qua, Vec3},
+};
+use rand::Rng;
+
+use crate::{color_obj::AgeObject, plane};
+
+use super::{config::LeavesConfig, Application};
+
+const TIP_AGE: f32 = 0.7;
+const SPAWN_TRIES: u32 = 8;
+const SWAY: f32 = 0.05;
+
+struct Leaf {
+    origin: Vec3,
+    height: f32,
+    ground: f32,
+    speed: f32,
+    phase: f32,
+    time: f32,
+}
+
+pub struct Leaves {
+    pub object: AgeObject,
+    leaves: Vec<Leaf>,
+}
+
+impl Leaves {
+    pub fn new(renderer: &mut dyn Renderer, config: &LeavesConfig) -> Self {
+        let instances = (0..config.count)
+            .map(|_| Instance::default().translate(Vec3::splat(-1000.0)))
+            .collect();
+        // age 1 so the shader colors them like the tips they fall from
+        let object = AgeObject::quad(renderer, 1.0, instances);
+        let leaves = (0..config.count)
+            .map(|_| Leaf {
+                origin: Vec3::ZERO,
+                height: 0.0,
+                ground: 0.0,
+                speed: 0.0,
+                phase: 0.0,
+                time: 0.0,
+            })
+            .collect();
+
+        Self { object, leaves }
+    }
+}
+
+impl Application {
+    pub fn update_leaves(&mut self, dt: f32, renderer: &mut dyn Renderer) {
+        let size = self.scene.leaves.size;
+        let plants = self.plants.values().collect::<Vec<_>>();
+        for (leaf, instance) in self
+            .leaves
+            .leaves
+            .iter_mut()
+            .zip(self.leaves.object.instances.iter_mut())
+        {
+            if leaf.height <= leaf.ground {
+                let Some(tip) = pick_tip(&plants, self.growth, &mut self.rng) else {
+                    instance.set_mat(Mat4::from_translation(Vec3::splat(-1000.0)));
+                    continue;
+                };
+                leaf.origin = tip;
+                leaf.height = tip.y;
+                leaf.ground =
+                    plane::height_at(&self.noise_image, self.scene.floor.height, tip.x, tip.z);
+                leaf.speed = self.rng.gen_range(0.05..0.15);
+                leaf.phase = self.rng.gen_range(0.0..std::f32::consts::TAU);
+                leaf.time = 0.0;
+            }
+
+            leaf.time += dt;
+            leaf.height -= leaf.speed * dt;
+
+            let t = leaf.time + leaf.phase;
+            let pos = Vec3::new(
+                leaf.origin.x + (t * 2.0).sin() * SWAY,
+                leaf.height,
+                leaf.origin.z + (t * 1.3).cos() * SWAY,
+            );
+            let rotation = Qua::from_axis_angle(Vec3::Y, leaf.phase)
+                * Qua::from_axis_angle(Vec3::X, (t * 3.0).sin());
+
+            instance.set_mat(Mat4::from_scale_rotation_translation(
+                Vec3::splat(size),
+                rotation,
+                pos,
+            ));
+        }
+
+        self.leaves.object.update(renderer);
+    }
+}
+
+// a random vertex near the end of a random grown plant, in world space
+fn pick_tip(plants: &[&AgeObject], growth: f32, rng: &mut impl Rng) -> Option<Vec3> {
+    if plants.is_empty() {
+        return None;
+    }
+
+    let plant = plants[rng.gen_range(0..plants.len())];
+    let origin = plant.instances.first()?.position();
+    (0..SPAWN_TRIES).find_map(|_| {
+        let vertex = plant
+            .vertices
+            .get(rng.gen_range(0..plant.vertices.len().max(1)))?;
+        (vertex.age >= TIP_AGE && vertex.age <= growth).then(|| origin + vertex.position)
+    })
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use jandering_engine::{
+    core::renderer::{get_typed_bind_group, Renderer},
+    types::{Vec2, Vec3},
+};
+
+use crate::color_obj::{AgeObject, AgeVertex};
+
+use super::{logic::camera_ground_intersection, plant_grid::Cell, Application};
+
+// hidden ages land this far past 1, so not even the very tip is left showing
+const HIDE_MARGIN: f32 = 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Detail {
+    Full,
+    // everything past the config's prune age is cut off
+    Reduced,
+}
+
+// fades whatever is past `threshold` in or out by rewriting the plant's vertices on the gpu,
+// the fragment shader already discards anything older than the growth so pushed ages disappear
+struct Fade {
+    threshold: f32,
+    // 0 hides everything past the threshold, 1 is the plain mesh
+    visibility: f32,
+    showing: bool,
+    // vertices past the threshold that share a triangle with one before it shrink onto it, so a
+    // segment straddling the threshold pulls back into its base instead of leaving a stub
+    collapse: Vec<Option<Vec3>>,
+}
+
+impl Fade {
+    fn new(plant: &AgeObject, threshold: f32, showing: bool) -> Self {
+        let mut collapse = vec![None; plant.vertices.len()];
+        for triangle in plant.indices.chunks_exact(3) {
+            let base = triangle
+                .iter()
+                .map(|e| plant.vertices[*e as usize])
+                .find(|e| e.age <= threshold);
+            let Some(base) = base else {
+                continue;
+            };
+            for e in triangle {
+                if plant.vertices[*e as usize].age > threshold {
+                    collapse[*e as usize].get_or_insert(base.position);
+                }
+            }
+        }
+
+        Self {
+            threshold,
+            visibility: if showing { 0.0 } else { 1.0 },
+            showing,
+            collapse,
+        }
+    }
+
+    // everything is past a threshold below 0, so the whole plant grows in like it does at startup
+    fn appear(plant: &AgeObject) -> Self {
+        Self::new(plant, -HIDE_MARGIN, true)
+    }
+
+    fn advance(&mut self, step: f32) -> bool {
+        if self.showing {
+            self.visibility = (self.visibility + step).min(1.0);
+            self.visibility >= 1.0
+        } else {
+            self.visibility = (self.visibility - step).max(0.0);
+            self.visibility <= 0.0
+        }
+    }
+
+    fn apply(&self, vertices: &[AgeVertex]) -> Vec<AgeVertex> {
+        let shift = (1.0 - self.visibility) * (1.0 - self.threshold + HIDE_MARGIN);
+        vertices
+            .iter()
+            .zip(&self.collapse)
+            .map(|(vertex, collapse)| match collapse {
+                _ if vertex.age <= self.threshold => *vertex,
+                Some(base) => AgeVertex {
+                    position: base.lerp(vertex.position, self.visibility),
+                    ..*vertex
+                },
+                None => AgeVertex {
+                    age: vertex.age + shift,
+                    ..*vertex
+                },
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct Lod {
+    details: HashMap<Cell, Detail>,
+    fades: HashMap<Cell, Fade>,
+}
+
+impl Lod {
+    // for a cell whose mesh got replaced from outside, it starts over as a full plant
+    pub fn forget(&mut self, cell: &Cell) {
+        self.details.remove(cell);
+        self.fades.remove(cell);
+    }
+}
+
+impl Application {
+    // a cell that already has a detail only switches once it's past the boundary by the
+    // hysteresis, so one hovering right at it doesn't flicker between the two
+    pub fn wanted_detail(&self, cell: Cell, ground: Vec2, current: Option<Detail>) -> Detail {
+        let config = &self.scene.lod;
+        if !config.enabled {
+            return Detail::Full;
+        }
+
+        let pos = self.plants.cell_position(cell);
+        let distance = Vec2::new(pos.x, pos.z).distance(ground);
+        let boundary = match current {
+            Some(Detail::Full) => config.distance + config.hysteresis,
+            Some(Detail::Reduced) => config.distance - config.hysteresis,
+            None => config.distance,
+        };
+        if distance > boundary {
+            Detail::Reduced
+        } else {
+            Detail::Full
+        }
+    }
+
+    // the mesh a freshly spawned plant starts with, it grows in instead of popping up
+    pub fn spawn_detail(
+        &mut self,
+        cell: Cell,
+        ground: Vec2,
+        vertices: Vec<AgeVertex>,
+        indices: Vec<u32>,
+    ) -> (Vec<AgeVertex>, Vec<u32>) {
+        let detail = self.wanted_detail(cell, ground, None);
+        self.lod.details.insert(cell, detail);
+        match detail {
+            Detail::Full => (vertices, indices),
+            Detail::Reduced => prune_above(&vertices, &indices, self.scene.lod.prune_age),
+        }
+    }
+
+    pub fn start_appear(&mut self, cell: Cell) {
+        if !self.scene.lod.enabled {
+            return;
+        }
+        if let Some(plant) = self.plants.get(&cell) {
+            self.lod.fades.insert(cell, Fade::appear(plant));
+        }
+    }
+
+    pub fn update_lod(&mut self, dt: f32, renderer: &mut dyn Renderer) {
+        let plants = &self.plants;
+        self.lod
+            .details
+            .retain(|cell, _| plants.get(cell).is_some());
+        self.lod.fades.retain(|cell, _| plants.get(cell).is_some());
+
+        let camera = get_typed_bind_group(renderer, self.camera).unwrap();
+        if let Some(ground) = camera_ground_intersection(camera.direction(), camera.position()) {
+            let ground = Vec2::new(ground.x, ground.z);
+            self.switch_details(ground, renderer);
+        }
+
+        let step = dt / self.scene.lod.fade_secs;
+        let mut finished = Vec::new();
+        for (cell, fade) in self.lod.fades.iter_mut() {
+            let Some(plant) = self.plants.get(cell) else {
+                continue;
+            };
+            if fade.advance(step) {
+                finished.push(*cell);
+            }
+            let vertices = fade.apply(&plant.vertices);
+            renderer.write_buffer(
+                plant.render_data.vertex_buffer,
+                bytemuck::cast_slice(&vertices),
+            );
+        }
+
+        // once faded out the hidden part is actually dropped, that's where the savings are
+        for cell in finished {
+            let fade = self.lod.fades.remove(&cell).unwrap();
+            if fade.showing {
+                continue;
+            }
+            let Some(plant) = self.plants.get(&cell) else {
+                continue;
+            };
+            let (vertices, indices) = prune_above(&plant.vertices, &plant.indices, fade.threshold);
+            self.replace_plant(cell, vertices, indices, renderer);
+            self.lod.details.insert(cell, Detail::Reduced);
+        }
+    }
+
+    fn switch_details(&mut self, ground: Vec2, renderer: &mut dyn Renderer) {
+        let prune_age = self.scene.lod.prune_age;
+        let cells = self.plants.cells().copied().collect::<Vec<_>>();
+        for cell in cells {
+            if self.lod.fades.contains_key(&cell) {
+                continue;
+            }
+
+            let current = self.lod.details.get(&cell).copied().unwrap_or(Detail::Full);
+            match (current, self.wanted_detail(cell, ground, Some(current))) {
+                (Detail::Full, Detail::Reduced) => {
+                    let plant = self.plants.get(&cell).unwrap();
+                    self.lod
+                        .fades
+                        .insert(cell, Fade::new(plant, prune_age, false));
+                }
+                // the full mesh comes back hidden past the prune age and fades in from there
+                (Detail::Reduced, Detail::Full) => {
+                    let Some(seed) = self.plants.seed(&cell) else {
+                        continue;
+                    };
+                    let variation = self.plants.variation(cell);
+                    let (vertices, indices) = self.new_cell_plant(seed, &variation);
+                    self.replace_plant(cell, vertices, indices, renderer);
+                    self.lod.details.insert(cell, Detail::Full);
+                    let plant = self.plants.get(&cell).unwrap();
+                    self.lod
+                        .fades
+                        .insert(cell, Fade::new(plant, prune_age, true));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // keeps the instances so a running regrow or the placement on the ground carries over
+    fn replace_plant(
+        &mut self,
+        cell: Cell,
+        vertices: Vec<AgeVertex>,
+        indices: Vec<u32>,
+        renderer: &mut dyn Renderer,
+    ) {
+        let Some(plant) = self.plants.get_mut(&cell) else {
+            return;
+        };
+        let instances = std::mem::take(&mut plant.instances);
+        *plant = AgeObject::new(renderer, vertices, indices, instances);
+    }
+}
+
+// drops every triangle reaching past `max_age` along with the vertices only they used
+fn prune_above(
+    vertices: &[AgeVertex],
+    indices: &[u32],
+    max_age: f32,
+) -> (Vec<AgeVertex>, Vec<u32>) {
+    let mut remap = vec![None; vertices.len()];
+    let mut kept = Vec::new();
+    let indices = indices
+        .chunks_exact(3)
+        .filter(|triangle| {
+            triangle
+                .iter()
+                .all(|e| vertices[*e as usize].age <= max_age)
+        })
+        .flatten()
+        .map(|e| {
+            *remap[*e as usize].get_or_insert_with(|| {
+                kept.push(vertices[*e as usize]);
+                kept.len() as u32 - 1
+            })
+        })
+        .collect();
+    (kept, indices)
+}
@@ -1,141 +1,327 @@
-use std::collections::HashMap;
-
 use jandering_engine::{
     core::{
+        bind_group::camera::free::FreeCameraController,
         object::Instance,
-        renderer::{get_typed_bind_group, Renderer},
+        renderer::{get_typed_bind_group, get_typed_bind_group_mut, Renderer},
     },
     types::{Mat4, Qua, Vec2, Vec3},
     utils::load_text,
 };
-use rand::{rngs::ThreadRng, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use wasm_bindgen::JsCast;
-use web_sys::{HtmlDivElement, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use web_sys::{
+    HtmlAnchorElement, HtmlCanvasElement, HtmlDivElement, HtmlInputElement, HtmlSelectElement,
+    HtmlTextAreaElement,
+};
 
 use crate::{
-    color_obj::{AgeObject, AgeVertex},
-    icosphere,
+    color_obj::{AgeObject, AgeVertex, MeshStats, PlantStats},
     image::Image,
-    l_system::{self, config::LConfig, RenderShape},
+    l_system::{self, config::LConfig},
+    mesh, plane,
+    timer::Timer,
 };
 
-use super::{cylinder, Application};
-
-const DUST_SCALE: Vec3 = Vec3::splat(0.0085);
-
-const N_PLANTS: u32 = 4;
-const PLANT_SPACING: i32 = 3;
-
-const GRASS_RANGE: f32 = 2.75;
-const GRASS_ITERATIONS: u32 = 12;
-const GRASS_HEIGHT: f32 = 0.1;
-const GRASS_WIDTH: f32 = 0.0075;
-
-lazy_static::lazy_static! {
-    static ref CYLINDER_DATA: (Vec<AgeVertex>, Vec<u32>) = gen_cylinder_data();
-}
-
-fn gen_cylinder_data() -> (Vec<AgeVertex>, Vec<u32>) {
-    let (vertices, indices) = cylinder::generate(3);
-    let vertices = vertices
-        .into_iter()
-        .map(AgeVertex::from)
-        .collect::<Vec<AgeVertex>>();
-    (vertices, indices)
-}
+use super::{
+    config::{GrassConfig, SceneConfig},
+    leaves::Leaves,
+    plant_grid::CellVariation,
+    quality::InstanceBudget,
+    setup::{
+        apply_camera_pose, create_camera_controller, create_dust, create_floor, create_grass,
+        create_water,
+    },
+    Application,
+};
 
-fn cylinder(age: f32, next_age: f32, mat: Mat4, index_offset: u32) -> (Vec<AgeVertex>, Vec<u32>) {
-    let (mut vertices, mut indices) = CYLINDER_DATA.clone();
-    vertices.iter_mut().enumerate().for_each(|(i, e)| {
-        if i % 2 == 0 {
-            e.age = age;
-        } else {
-            e.age = next_age;
-        }
-        e.position = mat.mul_vec4(e.position.extend(1.0)).truncate();
-    });
-    indices.iter_mut().for_each(|e| *e += index_offset);
-    (vertices, indices)
+// plants built since the last report. new_plant only counts them, so a grid full of empty plants
+// gets one message instead of one per cell
+#[derive(Default)]
+pub struct EmptyPlants {
+    built: u32,
+    empty: u32,
+    reason: Option<String>,
+    // the line shown under the config message, if any
+    shown: Option<String>,
 }
 
 impl Application {
-    pub fn update_config(&mut self) {
+    pub fn update_config(&mut self, renderer: &mut dyn Renderer) {
         if let Some(config) = self.read_lsystem() {
+            match SceneConfig::from_json(&config) {
+                Ok(scene) => self.apply_scene_config(scene, renderer),
+                Err(error) => {
+                    self.config_message = error;
+                    self.show_messages();
+                    return;
+                }
+            }
+
             match LConfig::from_json(config) {
                 Ok(l_config) => {
+                    let warnings = l_config.warnings();
+                    for warning in warnings.iter() {
+                        log::warn!("{warning}");
+                    }
+                    self.config_message = warnings.join("\n");
+                    // every plant gets built again, whatever they said before is stale
+                    self.empty_plants.shown = None;
+
                     self.l_config = l_config;
                     self.plants.clear();
-                    self.display_error("");
                 }
                 Err(error) => {
-                    self.display_error(&error);
+                    self.config_message = error;
                 }
             }
+            self.show_messages();
+        }
+    }
+
+    fn apply_scene_config(&mut self, scene: SceneConfig, renderer: &mut dyn Renderer) {
+        let floor_changed = scene.floor != self.scene.floor;
+        // grass gets placed again from scratch so it lands on the new ground
+        let budget = InstanceBudget::new(&scene);
+        let budget_changed = budget != InstanceBudget::new(&self.scene);
+        let grass_changed = scene.grass != self.scene.grass || floor_changed || budget_changed;
+
+        if scene.dust.color != self.scene.dust.color || budget_changed {
+            self.dust = create_dust(renderer, &scene.dust, budget.dust);
+        }
+
+        if scene.leaves.count != self.scene.leaves.count {
+            self.leaves = Leaves::new(renderer, &scene.leaves);
+        }
+
+        if floor_changed {
+            self.noise_image
+                .set_fbm(scene.floor.octaves, scene.floor.persistence);
+            self.floor = create_floor(renderer, &scene.floor, &self.noise_image);
+            self.water = create_water(renderer, &scene.floor, &self.noise_image);
+            self.plants.clear();
+        }
+
+        if scene.grid != self.scene.grid {
+            self.plants.set_layout(&scene.grid);
+        }
+
+        if scene.camera != self.scene.camera {
+            let aspect = {
+                let size = renderer.size();
+                size.x as f32 / size.y as f32
+            };
+            let controller = create_camera_controller(renderer, &scene.camera);
+            let camera = get_typed_bind_group_mut(renderer, self.camera).unwrap();
+            apply_camera_pose(camera, &scene.camera, aspect);
+            // the pose is back to orthographic, so the isometric controller goes back in charge
+            camera.controller = Some(controller);
+            self.camera_controller = Box::<FreeCameraController>::default();
+        }
+
+        // the ramp restarts whenever it's edited, including the first load if it isn't the default
+        if scene.growth != self.scene.growth {
+            self.growth = scene.growth.start;
+        }
+
+        if scene.grass.blade_segments != self.scene.grass.blade_segments {
+            self.grass = create_grass(renderer, &scene.grass);
+        }
+
+        self.scene = scene;
+
+        if grass_changed {
+            self.update_grass_count(renderer);
+        }
+    }
+
+    pub fn reset_camera(&mut self, renderer: &mut dyn Renderer) {
+        let aspect = {
+            let size = renderer.size();
+            size.x as f32 / size.y as f32
+        };
+        let camera = get_typed_bind_group_mut(renderer, self.camera).unwrap();
+        let (position, direction) = (camera.position(), camera.direction());
+        apply_camera_pose(camera, &self.scene.camera, aspect);
+        *camera.position_mut() = position;
+        *camera.direction_mut() = direction;
+        self.camera_resetting = true;
+    }
+
+    pub fn update_camera_reset(&mut self, dt: f32, renderer: &mut dyn Renderer) {
+        if !self.camera_resetting {
+            return;
+        }
+
+        let target_position = Vec3::from(self.scene.camera.position);
+        let target_direction = Vec3::from(self.scene.camera.direction);
+
+        let camera = get_typed_bind_group_mut(renderer, self.camera).unwrap();
+        let t = 1.0 - (-15.0 * dt).exp();
+        let position = camera.position().lerp(target_position, t);
+        let direction = camera.direction().lerp(target_direction, t).normalize();
+        *camera.position_mut() = position;
+        *camera.direction_mut() = direction;
+
+        if position.distance(target_position) < 0.001
+            && direction.distance(target_direction) < 0.001
+        {
+            *camera.position_mut() = target_position;
+            *camera.direction_mut() = target_direction;
+            self.camera_resetting = false;
         }
     }
+
+    pub fn toggle_follow(&mut self, renderer: &mut dyn Renderer) {
+        if self.following.is_some() {
+            self.following = None;
+            self.reset_camera(renderer);
+            return;
+        }
+
+        let camera = get_typed_bind_group(renderer, self.camera).unwrap();
+        let ground_pos =
+            camera_ground_intersection(camera.direction(), camera.position()).unwrap_or(Vec3::ZERO);
+        let ground_pos = Vec2::new(ground_pos.x, ground_pos.z);
+        self.following = self.plants.nearest(ground_pos).or(Some((0, 0)));
+        self.camera_resetting = false;
+    }
+
+    pub fn update_follow(&mut self, dt: f32, renderer: &mut dyn Renderer) {
+        let Some(cell) = self.following else {
+            return;
+        };
+
+        let plant = if let Some(gallery) = &self.gallery {
+            Some(&gallery.plants[gallery.selected].object)
+        } else {
+            self.plants.get(&cell)
+        };
+        let Some(plant) = plant else {
+            return;
+        };
+        // an empty plant, or one that hasn't grown anything yet, is followed to its origin
+        // instead of leaving the camera wherever it was
+        let origin = plant.instances.first().unwrap().position();
+        let top = plant.bounds(self.growth).map_or(origin, |(min, max)| {
+            origin + Vec3::new((min.x + max.x) / 2.0, max.y, (min.z + max.z) / 2.0)
+        });
+        let distance = (self.scene.camera.position[1] / self.scene.camera.direction[1]).abs();
+
+        let camera = get_typed_bind_group_mut(renderer, self.camera).unwrap();
+        let target = top - camera.direction() * distance;
+        let t = 1.0 - (-3.0 * dt).exp();
+        *camera.position_mut() = camera.position().lerp(target, t);
+    }
+
     pub fn spawn_new_plants(&mut self, renderer: &mut dyn Renderer) {
         let camera = get_typed_bind_group(renderer, self.camera).unwrap();
         if let Some(ground_pos) = camera_ground_intersection(camera.direction(), camera.position())
         {
-            let snapped_cam = (ground_pos / PLANT_SPACING as f32).round() * PLANT_SPACING as f32;
-
-            let half = N_PLANTS as i32 / 2;
-            self.plants.retain(|_, obj| {
-                let half = (half * PLANT_SPACING) as f32;
-                let pos = obj.instances.first().unwrap().position();
-                (pos.x - snapped_cam.x).abs() <= half && (pos.z - snapped_cam.z).abs() <= half
-            });
-
-            for x in -half..half {
-                for z in -half..half {
-                    let pos = (
-                        snapped_cam.x as i32 + x * PLANT_SPACING,
-                        snapped_cam.z as i32 + z * PLANT_SPACING,
-                    );
-
-                    #[allow(clippy::map_entry)]
-                    if !self.plants.contains_key(&pos) {
-                        let (vertices, indices) = self.new_plant(&mut self.rng.clone());
-
-                        let object = AgeObject::new(
-                            renderer,
-                            vertices,
-                            indices,
-                            vec![Instance::default().translate(Vec3::new(
-                                pos.0 as f32,
-                                0.0,
-                                pos.1 as f32,
-                            ))],
-                        );
-                        self.plants.insert(pos, object);
-                    }
-                }
+            let center = self.plants.cell_at(Vec2::new(ground_pos.x, ground_pos.z));
+            for cell in self
+                .plants
+                .recenter(center)
+                .into_iter()
+                .take(self.quality.plants_per_frame())
+            {
+                let variation = self.plants.variation(cell);
+                let seed = match self.plants.seed(&cell) {
+                    Some(seed) => seed,
+                    None if cell == center => self.picked_seed.take().unwrap_or(variation.seed),
+                    None => variation.seed,
+                };
+                let (vertices, indices) = self.new_cell_plant(seed, &variation);
+                let (vertices, indices) = self.spawn_detail(
+                    cell,
+                    Vec2::new(ground_pos.x, ground_pos.z),
+                    vertices,
+                    indices,
+                );
+
+                let mut pos = self.plants.cell_position(cell);
+                pos.y = self.ground_height(pos.x, pos.z);
+                let object = AgeObject::new(
+                    renderer,
+                    vertices,
+                    indices,
+                    vec![Instance::default().translate(pos)],
+                );
+                self.plants.insert(cell, seed, object);
+                self.start_appear(cell);
             }
         }
     }
 
-    pub fn new_plant(&mut self, rng: &mut ThreadRng) -> (Vec<AgeVertex>, Vec<u32>) {
-        // let timer = Timer::now("building took: ".to_string());
-        self.l_config.randomize_rule_sets(None, rng);
-        let shapes = l_system::build(&self.l_config, rng);
+    // a grid plant, with its cell's angle and size nudges on top of the seed
+    pub fn new_cell_plant(
+        &mut self,
+        seed: u64,
+        variation: &CellVariation,
+    ) -> (Vec<AgeVertex>, Vec<u32>) {
+        let angle_scale = self.l_config.rendering.angle_scale;
+        self.l_config.rendering.angle_scale *= variation.angle_scale;
+        let (mut vertices, indices, _) = self.new_plant(seed);
+        self.l_config.rendering.angle_scale = angle_scale;
+
+        vertices.iter_mut().for_each(|e| {
+            e.position *= variation.size;
+            e.base_height *= variation.size;
+        });
+        (vertices, indices)
+    }
 
-        let mut vertices = Vec::new();
-        let mut indices = Vec::new();
+    pub fn new_plant(&mut self, seed: u64) -> (Vec<AgeVertex>, Vec<u32>, PlantStats) {
+        let timer = Timer::now("building".to_string());
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.l_config.randomize_rule_sets(None, &mut rng);
+        let shapes = l_system::build(&self.l_config, &mut rng);
+        let build_ms = timer.elapsed_ms();
+        // the plant still gets created, it just has nothing to draw
+        self.empty_plants.built += 1;
+        if shapes.is_empty() {
+            self.empty_plants.empty += 1;
+            self.empty_plants.reason = Some(self.l_config.explain_empty());
+        }
+        let shape_count = shapes.len();
+
+        let timer = Timer::now("meshing".to_string());
+        let (vertices, indices) = mesh::build(shapes, self.scene.weld);
+
+        let mesh_stats = MeshStats::new(&vertices, &indices);
+        let stats = PlantStats {
+            build_ms,
+            mesh_ms: timer.elapsed_ms(),
+            shapes: shape_count,
+            vertices: mesh_stats.vertices,
+            triangles: mesh_stats.triangles,
+        };
+        log::info!("plant stats: {stats}");
+
+        (vertices, indices, stats)
+    }
 
-        // timer.print();
+    // the heightmap repeats, so moving the floor by whole tiles keeps it lined up with ground_height
+    pub fn update_floor(&mut self, renderer: &mut dyn Renderer) {
+        let camera = get_typed_bind_group(renderer, self.camera).unwrap();
+        let ground_pos =
+            camera_ground_intersection(camera.direction(), camera.position()).unwrap_or(Vec3::ZERO);
 
-        // let timer = Timer::now("meshing took: ".to_string());
+        let period = self.noise_image.period();
+        let snapped = (Vec2::new(ground_pos.x, ground_pos.z) / period).round() * period;
+        let target = Vec3::new(snapped.x, 0.0, snapped.y);
 
-        for shape in shapes {
-            let (mut new_vertices, mut new_indices) =
-                shape_to_mesh_data(shape, vertices.len() as u32);
-            vertices.append(&mut new_vertices);
-            indices.append(&mut new_indices);
+        let instance = self.floor.instances.first_mut().unwrap();
+        if instance.position() != target {
+            instance.set_mat(Mat4::from_translation(target));
+            self.floor.update(renderer);
         }
 
-        // timer.print();
-
-        (vertices, indices)
+        let target = Vec3::new(target.x, self.scene.water.height, target.z);
+        let instance = self.water.instances.first_mut().unwrap();
+        if instance.position() != target {
+            instance.set_mat(Mat4::from_translation(target));
+            self.water.update(renderer);
+        }
     }
 
     pub fn update_dust(&mut self, dt: f32, renderer: &mut dyn Renderer) {
@@ -145,6 +331,8 @@ impl Application {
         let ground_pos = Vec2::new(ground_pos.x, ground_pos.z);
 
         let idle_rotation = Qua::from_axis_angle(Vec3::Y, 3.0 * dt);
+        let dust_scale = Vec3::splat(self.scene.dust.size);
+        let shrink = self.scene.dust.size / self.scene.dust.lifetime_secs.max(f32::EPSILON);
 
         for dust in self.dust.instances.iter_mut() {
             let mat = dust.mat();
@@ -157,7 +345,7 @@ impl Application {
                 let offset = Vec2::from_angle(angle.to_radians()) * dist;
                 pos_2d = ground_pos + offset;
                 pos.y = self.rng.gen_range(-0.5..0.0);
-                scale = DUST_SCALE;
+                scale = dust_scale;
 
                 let angle = self.rng.gen_range(0.0f32..360.0f32);
                 rotation *= Qua::from_axis_angle(Vec3::Y, angle);
@@ -168,7 +356,7 @@ impl Application {
             pos.y += 0.1 * dt;
             pos.z = pos_2d.y;
 
-            scale -= DUST_SCALE.x * dt * 0.2;
+            scale -= shrink * dt;
 
             let mat = Mat4::from_scale_rotation_translation(scale, rotation, pos);
             dust.set_mat(mat);
@@ -183,29 +371,41 @@ impl Application {
             camera_ground_intersection(camera.direction(), camera.position()).unwrap_or(Vec3::ZERO);
         let ground_pos = Vec2::new(ground_pos.x, ground_pos.z);
 
+        let GrassConfig {
+            range,
+            iterations,
+            height,
+            width,
+            ..
+        } = self.scene.grass;
+
         for grass in self.grass.instances.iter_mut() {
             let mat = grass.mat();
-            let (_, rotation, mut pos) = mat.to_scale_rotation_translation();
+            let (_, mut rotation, mut pos) = mat.to_scale_rotation_translation();
             let mut pos_2d = Vec2::new(pos.x, pos.z);
-            if pos_2d.distance(ground_pos) > GRASS_RANGE {
+            if pos_2d.distance(ground_pos) > range {
                 let dist = self.rng.gen_range(0.9f32..1.0f32);
                 let angle = self.rng.gen_range(0.0f32..360.0f32);
 
-                let offset = Vec2::from_angle(angle.to_radians()) * dist * GRASS_RANGE;
+                let offset = Vec2::from_angle(angle.to_radians()) * dist * range;
                 pos_2d = ground_pos + offset;
 
                 let scale_mod = 0.7 + self.noise_image.sample(pos_2d.x, pos_2d.y) * 0.6;
-                let mut scale = Vec3::new(GRASS_WIDTH, GRASS_HEIGHT, 1.0) * scale_mod;
+                let mut scale = Vec3::new(width, height, 1.0) * scale_mod;
                 pos.x = pos_2d.x;
                 pos.z = pos_2d.y;
 
                 pos = Self::place_pos_on_heightmap(
                     pos,
-                    GRASS_ITERATIONS,
+                    iterations,
                     &self.noise_image,
                     &mut self.rng,
                 );
-                pos.y = 0.0;
+                pos.y = plane::height_at(&self.noise_image, self.scene.floor.height, pos.x, pos.z);
+                let normal = self
+                    .noise_image
+                    .sample_normal(pos.x, pos.z, self.scene.floor.height);
+                rotation = Qua::from_rotation_arc(Vec3::Y, normal);
                 if (Vec3::ZERO).distance(pos) < 3.0 {
                     scale *= 0.01;
                 }
@@ -218,11 +418,15 @@ impl Application {
         self.grass.update(renderer);
     }
 
+    pub fn ground_height(&self, x: f32, z: f32) -> f32 {
+        plane::height_at(&self.noise_image, self.scene.floor.height, x, z)
+    }
+
     pub fn place_pos_on_heightmap(
         mut pos: Vec3,
         iterations: u32,
         heightmap: &Image,
-        rng: &mut ThreadRng,
+        rng: &mut impl Rng,
     ) -> Vec3 {
         for _ in 0..=iterations {
             let mut highest_val = heightmap.sample(pos.x, pos.z);
@@ -287,6 +491,40 @@ impl Application {
         }
     }
 
+    // once a frame, the message stays up while plants keep coming out empty and goes away with the
+    // first batch that all drew something
+    pub fn report_empty_plants(&mut self) {
+        let plants = &mut self.empty_plants;
+        if plants.built == 0 {
+            return;
+        }
+
+        let shown = plants.reason.take().map(|reason| {
+            log::warn!(
+                "{} of {} plants have no shapes: {reason}",
+                plants.empty,
+                plants.built
+            );
+            format!("the plant has no shapes: {reason}")
+        });
+        plants.built = 0;
+        plants.empty = 0;
+
+        if shown != plants.shown {
+            plants.shown = shown;
+            self.show_messages();
+        }
+    }
+
+    fn show_messages(&self) {
+        let message = std::iter::once(self.config_message.as_str())
+            .chain(self.empty_plants.shown.as_deref())
+            .filter(|e| !e.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.display_error(&message);
+    }
+
     fn display_error(&self, string: &str) -> Option<()> {
         let window = web_sys::window()?;
         let document = window.document()?;
@@ -299,6 +537,35 @@ impl Application {
     }
 }
 
+pub fn screenshot_file_name(index: u32) -> String {
+    let millis = web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .map(|e| e.as_millis())
+        .unwrap_or_default();
+    format!("screenshot_{millis}_{index}.png")
+}
+
+pub fn save_canvas(file_name: &str) -> Option<()> {
+    let document = web_sys::window()?.document()?;
+    let canvas = document
+        .query_selector("#jandering-engine-canvas-body canvas")
+        .ok()??
+        .dyn_into::<HtmlCanvasElement>()
+        .ok()?;
+    let data = canvas.to_data_url_with_type("image/png").ok()?;
+
+    let link = document
+        .create_element("a")
+        .ok()?
+        .dyn_into::<HtmlAnchorElement>()
+        .ok()?;
+    link.set_href(&data);
+    link.set_download(file_name);
+    link.click();
+
+    Some(())
+}
+
 pub fn read_lut(linear: bool) -> Option<Vec<Vec3>> {
     let elements = web_sys::window()?
         .document()?
@@ -334,7 +601,7 @@ pub fn read_lut(linear: bool) -> Option<Vec<Vec3>> {
     Some(colors)
 }
 
-fn camera_ground_intersection(dir: Vec3, cam_pos: Vec3) -> Option<Vec3> {
+pub fn camera_ground_intersection(dir: Vec3, cam_pos: Vec3) -> Option<Vec3> {
     let denom = Vec3::Y.dot(-dir);
     if denom > 1e-6 {
         let dif = -cam_pos;
@@ -345,36 +612,8 @@ fn camera_ground_intersection(dir: Vec3, cam_pos: Vec3) -> Option<Vec3> {
     }
 }
 
-fn shape_to_mesh_data(shape: RenderShape, vertices_len: u32) -> (Vec<AgeVertex>, Vec<u32>) {
-    let (vertices, indices) = match shape {
-        RenderShape::Line {
-            start,
-            end,
-            width,
-            age,
-            last_age,
-        } => {
-            let diff = end - start;
-            let length = diff.length();
-            let width = width * length * 0.01;
-            let mat = Mat4::from_scale_rotation_translation(
-                Vec3::new(width, length, width),
-                Qua::from_rotation_arc(Vec3::Y, diff.normalize()),
-                start + diff * 0.5,
-            );
-            let (vertices, indices) = cylinder(last_age, age, mat, vertices_len);
-            (vertices, indices)
-        }
-        RenderShape::Circle { size, pos, age } => {
-            let mat = Mat4::from_scale_rotation_translation(Vec3::splat(size), Qua::default(), pos);
-            let (vertices, indices) = icosphere::generate(age, mat, vertices_len);
-            (vertices, indices)
-        }
-    };
-    (vertices, indices)
-}
-
-pub async fn setups_js_inputs() -> Option<HashMap<String, String>> {
+// in the order presets.json lists them
+pub async fn setups_js_inputs() -> Option<Vec<(String, String)>> {
     let window = web_sys::window()?;
     let document = window.document()?;
     let presets_input = document
@@ -390,7 +629,7 @@ pub async fn setups_js_inputs() -> Option<HashMap<String, String>> {
         return None;
     }
 
-    let mut map = HashMap::new();
+    let mut texts = Vec::with_capacity(presets.len());
 
     presets_input.set_value(&presets[0]);
     let _ = presets_input.set_attribute("changed", "");
@@ -404,7 +643,7 @@ pub async fn setups_js_inputs() -> Option<HashMap<String, String>> {
         )))
         .await
         .unwrap();
-        map.insert(preset, text);
+        texts.push((preset, text));
     }
-    Some(map)
+    Some(texts)
 }
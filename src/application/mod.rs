@@ -7,43 +7,64 @@ use jandering_engine::{
         },
         engine::{Engine, EngineContext},
         event_handler::EventHandler,
-        object::{Instance, Object, Renderable, Vertex},
+        object::Renderable,
         renderer::{
             create_typed_bind_group, get_typed_bind_group, get_typed_bind_group_mut,
             BindGroupHandle, Renderer, SamplerHandle, ShaderHandle, TextureHandle,
         },
-        shader::ShaderDescriptor,
         texture::{TextureDescriptor, TextureFormat},
         window::{Key, WindowEvent},
     },
-    types::Vec2,
-    utils::load_text,
+    types::{Vec2, Vec3},
 };
 use rand::{rngs::ThreadRng, thread_rng};
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
+use std::collections::HashMap;
 
 use crate::{
-    color_obj::AgeObject, cylinder, image::Image, l_system::config::LConfig,
-    render_data::RenderDataBindGroup,
+    color_obj::AgeObject, image::Image, l_system::config::LConfig, render_data::RenderDataBindGroup,
 };
 
 use self::{
-    logic::setups_js_inputs,
-    setup::{create_camera, create_lut_textures, create_objects, create_shaders, create_textures},
+    config::{GrowthCurve, SceneConfig},
+    cursor_wind::{wind_phase, CursorWind},
+    day_night::Sun,
+    gallery::Gallery,
+    leaves::Leaves,
+    lod::Lod,
+    logic::{save_canvas, screenshot_file_name, setups_js_inputs, EmptyPlants},
+    persist::{restore_last_plant, Persist},
+    plant_grid::{Cell, PlantGrid},
+    quality::Quality,
+    recording::Recording,
+    regrow::Regrowing,
+    setup::{
+        create_camera, create_lut_textures, create_objects, create_shaders, create_textures,
+        create_water,
+    },
+    shaders::ShaderRegistry,
+    species::{parse_species, Species},
+    stats::Stats,
+    tweaks::Tweaks,
 };
 
+pub mod config;
+pub mod cursor_wind;
+pub mod day_night;
+pub mod gallery;
+pub mod leaves;
+pub mod lod;
 pub mod logic;
+pub mod persist;
+pub mod plant_grid;
+pub mod quality;
+pub mod recording;
+pub mod regrow;
+pub mod seasons;
 pub mod setup;
-
-lazy_static::lazy_static! {
-    #[derive(Debug)]
-    pub static ref SHADER_CODE_MUTEX: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-}
-
-type Plants = HashMap<(i32, i32), AgeObject>;
+pub mod shaders;
+pub mod species;
+pub mod stats;
+pub mod tweaks;
 
 pub struct Application {
     last_time: web_time::Instant,
@@ -51,18 +72,28 @@ pub struct Application {
     shader: ShaderHandle,
     floor_shader: ShaderHandle,
     grass_shader: ShaderHandle,
+    sky_shader: ShaderHandle,
+    outline_shader: ShaderHandle,
+    water_shader: ShaderHandle,
     camera: BindGroupHandle<MatrixCameraBindGroup>,
     camera_controller: Box<dyn CameraController>,
     depth_texture: TextureHandle,
 
-    plants: Plants,
+    plants: PlantGrid,
     l_config: LConfig,
+    scene: SceneConfig,
     presets: HashMap<String, String>,
-    floor: Object<Instance>,
+    species: Vec<Species>,
+    species_index: usize,
+    floor: AgeObject,
+    water: AgeObject,
 
     dust: AgeObject,
     dust_shader: ShaderHandle,
+    leaves: Leaves,
+    leaf_shader: ShaderHandle,
     grass: AgeObject,
+    sky: AgeObject,
     noise_image: Image,
     noise_texture: BindGroupHandle<TextureBindGroup>,
 
@@ -71,47 +102,106 @@ pub struct Application {
     lut_sampler: SamplerHandle,
 
     render_data: BindGroupHandle<RenderDataBindGroup>,
+    grass_render_data: BindGroupHandle<RenderDataBindGroup>,
 
     rng: ThreadRng,
 
     randomize_rule_sets_timer: f32,
+
+    gallery: Option<Gallery>,
+    picked_seed: Option<u64>,
+
+    screenshot_requested: bool,
+    screenshot_count: u32,
+
+    // what the error box says about the config, empty plant reports go under it
+    config_message: String,
+    empty_plants: EmptyPlants,
+
+    recording: Option<Recording>,
+
+    paused: bool,
+    growth: f32,
+
+    camera_resetting: bool,
+    following: Option<Cell>,
+
+    shaders: ShaderRegistry,
+    stats: Stats,
+    tweaks: Tweaks,
+    quality: Quality,
+    skip_frame: bool,
+    cursor_wind: CursorWind,
+    regrowing: Regrowing,
+    persist: Persist,
+    lod: Lod,
 }
 
 const N_DUST: u32 = 60;
-const N_GRASS: u32 = 5000;
 
-const REFERENCE_DIAGONAL: f32 = 2202.0;
-const ORTHO_WIDTH: f32 = 2.0;
-const ORTHO_HEIGHT: f32 = ORTHO_WIDTH;
 const ORTHO_NEAR: f32 = 0.003;
 const ORTHO_FAR: f32 = 1000.0;
 
 const RANDOMIZE_RULE_SETS_TIME_SECS: f32 = 10.0;
 
+const GROWTH_STEP: f32 = 0.05;
+
+// display refresh jitters a little, without this a 60 fps cap would drop to 30 on a 60 hz screen
+const FRAME_SLACK_SECS: f32 = 0.002;
+
 impl Application {
     pub async fn new(engine: &mut Engine) -> Self {
-        let (shader, floor_shader, grass_shader, dust_shader) =
-            create_shaders(engine.renderer.as_mut()).await;
+        let (
+            shaders,
+            shader,
+            floor_shader,
+            grass_shader,
+            dust_shader,
+            leaf_shader,
+            sky_shader,
+            outline_shader,
+            water_shader,
+        ) = create_shaders(engine.renderer.as_mut()).await;
+
+        let scene = SceneConfig::default();
 
         let (
             depth_texture,
-            noise_image,
+            mut noise_image,
             noise_texture,
             lut_sampler,
             lut_texture,
             lut_texture_linear,
-        ) = create_textures(engine.renderer.as_mut()).await;
+        ) = create_textures(engine.renderer.as_mut(), scene.lut_resolution).await;
 
-        let (plants, floor, dust, grass) = create_objects(engine.renderer.as_mut());
+        noise_image.set_fbm(scene.floor.octaves, scene.floor.persistence);
+
+        let leaves = Leaves::new(engine.renderer.as_mut(), &scene.leaves);
+
+        let (plants, floor, dust, grass, sky) = create_objects(
+            engine.renderer.as_mut(),
+            &scene.grass,
+            &scene.grid,
+            &scene.floor,
+            &scene.dust,
+            &noise_image,
+        );
+        let water = create_water(engine.renderer.as_mut(), &scene.floor, &noise_image);
 
         let l_config = LConfig::default();
 
-        let presets = setups_js_inputs().await.unwrap_or(HashMap::new());
+        let presets = setups_js_inputs().await.unwrap_or_default();
+        let species = parse_species(&presets);
+        let presets = presets.into_iter().collect::<HashMap<_, _>>();
+        let picked_seed = restore_last_plant();
 
         let render_data = RenderDataBindGroup::new(engine.renderer.as_mut());
         let render_data = create_typed_bind_group(engine.renderer.as_mut(), render_data);
+        let grass_render_data = RenderDataBindGroup::new(engine.renderer.as_mut());
+        let grass_render_data =
+            create_typed_bind_group(engine.renderer.as_mut(), grass_render_data);
 
-        let camera = create_camera(engine.renderer.as_mut());
+        let camera = create_camera(engine.renderer.as_mut(), &scene.camera);
 
         let rng = thread_rng();
 
@@ -125,15 +215,25 @@ impl Application {
 
             grass_shader,
             floor_shader,
+            sky_shader,
+            outline_shader,
+            water_shader,
 
             plants,
             l_config,
+            scene,
             presets,
+            species,
+            species_index: 0,
             floor,
+            water,
 
             dust,
             dust_shader,
+            leaves,
+            leaf_shader,
             grass,
+            sky,
             noise_image,
             noise_texture,
 
@@ -142,10 +242,38 @@ impl Application {
             lut_sampler,
 
             render_data,
+            grass_render_data,
 
             rng,
 
             randomize_rule_sets_timer: RANDOMIZE_RULE_SETS_TIME_SECS,
+
+            gallery: None,
+            picked_seed,
+
+            screenshot_requested: false,
+            screenshot_count: 0,
+
+            config_message: String::new(),
+            empty_plants: EmptyPlants::default(),
+
+            recording: None,
+
+            paused: false,
+            growth: 1.0,
+
+            camera_resetting: false,
+            following: None,
+
+            shaders,
+            stats: Stats::default(),
+            tweaks: Tweaks::default(),
+            quality: Quality::default(),
+            skip_frame: false,
+            cursor_wind: CursorWind::default(),
+            regrowing: Regrowing::default(),
+            persist: Persist::default(),
+            lod: Lod::default(),
         }
     }
 }
@@ -153,35 +281,26 @@ impl Application {
 impl EventHandler for Application {
     fn on_update(&mut self, context: &mut EngineContext) {
         let current_time = web_time::Instant::now();
-        let dt = (current_time - self.last_time).as_secs_f32();
-        self.last_time = current_time;
-        self.time += dt;
-
-        let mut guard = SHADER_CODE_MUTEX.lock().unwrap();
-        if let Some(code) = guard.clone() {
-            context.renderer.create_shader_at(
-                ShaderDescriptor::default()
-                    .with_source(jandering_engine::core::shader::ShaderSource::Code(code))
-                    .with_descriptors(vec![Vertex::desc(), Instance::desc()])
-                    .with_bind_group_layouts(vec![MatrixCameraBindGroup::get_layout()])
-                    .with_depth(true)
-                    .with_backface_culling(true),
-                self.shader,
-            );
-            *guard = None;
+        let elapsed = (current_time - self.last_time).as_secs_f32();
+        // skipped frames still handle input but advance nothing, the time carries over to the next one
+        self.skip_frame =
+            self.scene.max_fps > 0 && elapsed < 1.0 / self.scene.max_fps as f32 - FRAME_SLACK_SECS;
+        let dt = if self.skip_frame {
+            0.0
+        } else {
+            self.last_time = current_time;
+            elapsed
+        };
+        let frozen = self.paused || self.recording.is_some();
+        if !frozen {
+            self.time += dt;
         }
 
+        self.shaders.update(context.renderer.as_mut());
+        self.cursor_wind.track(context.events);
+
         if context.events.is_pressed(Key::V) {
-            wasm_bindgen_futures::spawn_local(async move {
-                let text = load_text(jandering_engine::utils::FilePath::FileName(
-                    "shaders/shader.wgsl",
-                ))
-                .await
-                .unwrap();
-
-                let mut guard = SHADER_CODE_MUTEX.lock().unwrap();
-                *guard = Some(text);
-            });
+            self.shaders.reload();
         }
 
         if context.events.is_pressed(Key::F) {
@@ -209,11 +328,13 @@ impl EventHandler for Application {
                 camera.controller.as_mut().unwrap(),
                 &mut self.camera_controller,
             );
+            let width = self.scene.camera.ortho_width;
+            let height = width;
             camera.make_ortho(
-                (-ORTHO_WIDTH * aspect) / 2.0,
-                (ORTHO_WIDTH * aspect) / 2.0,
-                5.0 - ORTHO_HEIGHT / 2.0,
-                ORTHO_HEIGHT / 2.0,
+                (-width * aspect) / 2.0,
+                (width * aspect) / 2.0,
+                5.0 - height / 2.0,
+                height / 2.0,
                 ORTHO_NEAR,
                 ORTHO_FAR,
             );
@@ -228,11 +349,13 @@ impl EventHandler for Application {
                 size.x as f32 / size.y as f32
             };
             let camera = get_typed_bind_group_mut(context.renderer.as_mut(), self.camera).unwrap();
+            let width = self.scene.camera.ortho_width;
+            let height = width;
             camera.make_ortho(
-                (-ORTHO_WIDTH * aspect) / 2.0,
-                (ORTHO_WIDTH * aspect) / 2.0,
-                -ORTHO_HEIGHT / 2.0,
-                ORTHO_HEIGHT / 2.0,
+                (-width * aspect) / 2.0,
+                (width * aspect) / 2.0,
+                -height / 2.0,
+                height / 2.0,
                 ORTHO_NEAR,
                 ORTHO_FAR,
             );
@@ -247,48 +370,246 @@ impl EventHandler for Application {
             );
         }
 
+        if context.events.is_pressed(Key::C) {
+            self.following = None;
+            self.reset_camera(context.renderer.as_mut());
+        }
+
+        if context.events.is_pressed(Key::H) {
+            self.toggle_follow(context.renderer.as_mut());
+        }
+
         let camera = get_typed_bind_group_mut(context.renderer.as_mut(), self.camera).unwrap();
         camera.update(context.events, dt);
+        self.update_camera_reset(dt, context.renderer.as_mut());
+        self.update_follow(dt, context.renderer.as_mut());
+
+        if context.events.is_pressed(Key::T) {
+            self.paused = !self.paused;
+            log::info!("{}", if self.paused { "paused" } else { "unpaused" });
+        }
+
+        if self.paused {
+            if context.events.is_pressed(Key::Y) {
+                self.growth = if self.growth >= 1.0 {
+                    0.0
+                } else {
+                    (self.growth + GROWTH_STEP).min(1.0)
+                };
+                log::info!("growth: {}", self.growth);
+            }
+        } else {
+            let ramp = &self.scene.growth;
+            self.growth = match ramp.curve {
+                // 4 time constants leave it within 2% of grown once the duration is up
+                GrowthCurve::Ease => {
+                    self.growth + (1.0 - self.growth) * (1.0 - (-4.0 / ramp.duration * dt).exp())
+                }
+                GrowthCurve::Linear => (self.growth + dt / ramp.duration).min(1.0),
+            };
+            if self.growth > 0.999 {
+                self.growth = 1.0;
+            }
+            self.randomize_rule_sets_timer -= dt;
+        }
 
-        self.randomize_rule_sets_timer -= dt;
         if self.randomize_rule_sets_timer < 0.0 {
             self.l_config.randomize_rule_sets(Some(1), &mut self.rng);
             self.randomize_rule_sets_timer = RANDOMIZE_RULE_SETS_TIME_SECS;
             log::info!("randomized rule sets");
         }
 
-        self.update_config();
-        self.spawn_new_plants(context.renderer.as_mut());
-        self.update_dust(dt, context.renderer.as_mut());
+        if context.events.is_pressed(Key::P) {
+            self.screenshot_requested = true;
+        }
+
+        if context.events.is_pressed(Key::L) {
+            self.start_recording();
+        }
+
+        if context.events.is_pressed(Key::B) {
+            self.toggle_gallery(context.renderer.as_mut());
+        }
+
+        if context.events.is_pressed(Key::I) {
+            self.toggle_stats();
+        }
+
+        if context.events.is_pressed(Key::K) {
+            self.toggle_tweaks();
+        }
+
+        if context.events.is_pressed(Key::N) {
+            self.next_species();
+        }
+
+        if context.events.is_pressed(Key::U) {
+            self.scene.max_fps = match self.scene.max_fps {
+                0 => 30,
+                1..=30 => 60,
+                _ => 0,
+            };
+            log::info!("max fps: {}", self.scene.max_fps);
+        }
+
+        self.update_config(context.renderer.as_mut());
+        if self.gallery.is_some() {
+            self.update_gallery(dt, context.events, context.renderer.as_mut());
+        } else {
+            self.handle_regrow_clicks(context.events, context.renderer.as_mut());
+            self.spawn_new_plants(context.renderer.as_mut());
+            self.update_regrow(dt, context.renderer.as_mut());
+            self.update_lod(dt, context.renderer.as_mut());
+        }
+        self.report_empty_plants();
+        if !frozen {
+            self.update_dust(dt, context.renderer.as_mut());
+            self.update_leaves(dt, context.renderer.as_mut());
+        }
         self.update_grass(context.renderer.as_mut());
+        self.update_floor(context.renderer.as_mut());
 
+        let season = seasons::season_lut(
+            &self.scene.seasons,
+            &self.l_config.rendering,
+            self.time,
+            self.scene.lut_resolution,
+        );
         create_lut_textures(
             context.renderer.as_mut(),
+            self.scene.lut_resolution,
+            season,
+            &self.l_config.rendering.luts(),
             Some(self.lut_texture),
             Some(self.lut_texture_linear),
             Some(self.lut_sampler),
         );
 
         self.update_iteration_count();
+        if !self.skip_frame {
+            self.update_stats(dt);
+            self.update_quality(dt, context.renderer.as_mut());
+            self.update_persist(dt);
+        }
+        self.apply_tweaks(context.renderer.as_mut());
+
+        let sun = Sun::new(&self.scene.day_night, &self.scene.sky);
+
+        let diagonal = {
+            let size = context.renderer.size();
+            Vec2::new(size.x as f32, size.y as f32).length()
+        };
+        let camera_direction = get_typed_bind_group(context.renderer.as_ref(), self.camera)
+            .unwrap()
+            .direction();
+        self.cursor_wind
+            .update(&self.scene.cursor_wind, dt, diagonal, camera_direction);
 
         let render_data =
             get_typed_bind_group_mut(context.renderer.as_mut(), self.render_data).unwrap();
         render_data.data.time = self.time;
-        render_data.data.wind_strength = 0.002 + (self.time * 0.2).sin().powf(4.0).max(0.0) * 0.01;
+        render_data.data.emissive_intensity = if self.scene.glow.enabled {
+            self.scene.glow.intensity
+        } else {
+            0.0
+        };
+        render_data.data.tone_mapping = if self.scene.tone_mapping.enabled {
+            1.0
+        } else {
+            0.0
+        };
+        render_data.data.exposure = self.scene.tone_mapping.exposure;
+        render_data.data.fog_density = self.scene.fog.density;
+        render_data.data.fog_color = Vec3::from(self.scene.fog.color);
+        render_data.data.sky_horizon = sun.horizon;
+        render_data.data.sky_zenith = sun.zenith;
+        let light = &self.scene.light;
+        render_data.data.light_direction = if light.enabled && !self.scene.day_night.enabled {
+            Vec3::from(light.direction)
+        } else {
+            sun.direction
+        };
+        render_data.data.light_intensity = if light.enabled {
+            sun.intensity * light.intensity
+        } else {
+            sun.intensity
+        };
+        render_data.data.light_color = Vec3::from(light.color);
+        render_data.data.light_enabled = if light.enabled { 1.0 } else { 0.0 };
+        render_data.data.ambient = light.ambient;
+        render_data.data.ao_strength = if self.scene.ao.enabled {
+            self.scene.ao.strength
+        } else {
+            0.0
+        };
+        render_data.data.ao_radius = self.scene.ao.radius;
+        render_data.data.dust_size = self.scene.dust.size;
+        render_data.data.outline_color = Vec3::from(self.scene.outline.color);
+        render_data.data.outline_width = self.scene.outline.width;
+        render_data.data.gust_x = self.cursor_wind.gust.x;
+        render_data.data.gust_z = self.cursor_wind.gust.z;
+        render_data.data.water_color = Vec3::from(self.scene.water.color);
+        render_data.data.water_ripple = self.scene.water.ripple;
+        let field = &self.scene.wind_field;
+        render_data.data.wind_field_scale = field.scale;
+        render_data.data.wind_field_speed = field.speed;
+        render_data.data.wind_field_strength = if field.enabled { field.strength } else { 0.0 };
+        render_data.data.growth = self.recording.as_ref().map_or(self.growth, |e| e.growth());
+        let phase = wind_phase(self.scene.wind_seed);
+        render_data.data.wind_phase = phase;
+        render_data.data.wind_strength =
+            0.002 + ((self.time + phase) * 0.2).sin().powf(4.0).max(0.0) * 0.01;
+
+        let mut data = render_data.data;
+        let wind = &self.scene.grass_wind;
+        data.wind_strength *= wind.strength;
+        data.wind_scale *= wind.scale;
+        data.wind_speed *= wind.speed;
+        get_typed_bind_group_mut(context.renderer.as_mut(), self.grass_render_data)
+            .unwrap()
+            .data = data;
     }
 
     fn on_render(&mut self, renderer: &mut Box<dyn Renderer>) {
+        if self.skip_frame {
+            return;
+        }
+
         let camera = get_typed_bind_group(renderer.as_ref(), self.camera).unwrap();
         renderer.write_bind_group(self.camera.into(), &camera.get_data());
 
         let render_data = get_typed_bind_group(renderer.as_ref(), self.render_data).unwrap();
         renderer.write_bind_group(self.render_data.into(), &render_data.get_data());
 
-        let plants = self
-            .plants
-            .values()
-            .map(|e| e as &dyn Renderable)
-            .collect::<Vec<_>>();
+        let grass_render_data =
+            get_typed_bind_group(renderer.as_ref(), self.grass_render_data).unwrap();
+        renderer.write_bind_group(self.grass_render_data.into(), &grass_render_data.get_data());
+
+        let plants = if let Some(gallery) = &self.gallery {
+            gallery
+                .plants
+                .iter()
+                .map(|e| &e.object as &dyn Renderable)
+                .collect::<Vec<_>>()
+        } else {
+            self.plants
+                .values()
+                .map(|e| e as &dyn Renderable)
+                .collect::<Vec<_>>()
+        };
+
+        let outlined: &[&dyn Renderable] = if self.scene.outline.enabled {
+            &plants
+        } else {
+            &[]
+        };
+
+        let water_objects = [&self.water as &dyn Renderable];
+        let water: &[&dyn Renderable] = if self.scene.water.enabled {
+            &water_objects
+        } else {
+            &[]
+        };
 
         renderer
             .new_pass()
@@ -300,13 +621,36 @@ impl EventHandler for Application {
             .bind(2, self.noise_texture.into())
             .bind(3, self.lut_texture.into())
             .render(&[&self.floor])
+            .set_shader(self.water_shader)
+            .render(water)
             .set_shader(self.shader)
             .render(&plants)
+            .set_shader(self.outline_shader)
+            .render(outlined)
+            .bind(1, self.grass_render_data.into())
             .set_shader(self.dust_shader)
             .render(&[&self.dust])
+            .bind(1, self.render_data.into())
+            .set_shader(self.leaf_shader)
+            .render(&[&self.leaves.object])
+            .bind(1, self.grass_render_data.into())
             .bind(3, self.lut_texture_linear.into())
             .set_shader(self.grass_shader)
             .render(&[&self.grass])
+            .set_shader(self.sky_shader)
+            .render(&[&self.sky])
             .submit();
+
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            let file_name = screenshot_file_name(self.screenshot_count);
+            match save_canvas(&file_name) {
+                Some(_) => log::info!("saved screenshot to {file_name}"),
+                None => log::warn!("couldn't save screenshot"),
+            }
+            self.screenshot_count += 1;
+        }
+
+        self.capture_recording_frame();
     }
 }
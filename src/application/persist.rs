@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlSelectElement, HtmlTextAreaElement, Storage};
+
+use crate::l_system::config::LConfig;
+
+use super::Application;
+
+const STORAGE_KEY: &str = "cityscaper-last-plant";
+const SAVE_INTERVAL_SECS: f32 = 5.0;
+
+// the rule sets come from the seed, so preset, config and seed are enough to grow the same plant
+#[derive(Serialize, Deserialize)]
+struct SavedPlant {
+    preset: String,
+    config: String,
+    seed: u64,
+}
+
+#[derive(Default)]
+pub struct Persist {
+    timer: f32,
+    last_saved: Option<String>,
+}
+
+impl Application {
+    // written every few seconds instead of on exit, a closed tab doesn't reliably get to run anything
+    pub fn update_persist(&mut self, dt: f32) {
+        self.persist.timer -= dt;
+        if self.persist.timer > 0.0 {
+            return;
+        }
+        self.persist.timer = SAVE_INTERVAL_SECS;
+
+        let Some(seed) = self
+            .plants
+            .center()
+            .and_then(|cell| self.plants.seed(&cell))
+        else {
+            return;
+        };
+        let Some((preset, config)) = read_inputs() else {
+            return;
+        };
+        let Ok(saved) = serde_json::to_string(&SavedPlant {
+            preset,
+            config,
+            seed,
+        }) else {
+            return;
+        };
+
+        if self.persist.last_saved.as_ref() == Some(&saved) {
+            return;
+        }
+        if storage()
+            .and_then(|e| e.set_item(STORAGE_KEY, &saved).ok())
+            .is_none()
+        {
+            log::warn!("couldn't save the current plant");
+        }
+        self.persist.last_saved = Some(saved);
+    }
+}
+
+// puts the saved config in the editor as if it was just typed in and returns the seed for the
+// plant in the middle, anything that doesn't parse is dropped and the first preset loads as usual
+pub fn restore_last_plant() -> Option<u64> {
+    let saved = storage()?.get_item(STORAGE_KEY).ok()??;
+    let saved = match serde_json::from_str::<SavedPlant>(&saved) {
+        Ok(saved) => saved,
+        Err(error) => {
+            log::warn!("ignoring saved plant: {error}");
+            return None;
+        }
+    };
+    if let Err(error) = LConfig::from_json(saved.config.clone()) {
+        log::warn!("ignoring saved plant: {error}");
+        return None;
+    }
+
+    let document = web_sys::window()?.document()?;
+    let presets = document
+        .get_element_by_id("presets")?
+        .dyn_into::<HtmlSelectElement>()
+        .ok()?;
+    let edit = document
+        .get_element_by_id("edit-text-input")?
+        .dyn_into::<HtmlTextAreaElement>()
+        .ok()?;
+
+    presets.set_value(&saved.preset);
+    let _ = presets.remove_attribute("changed");
+    edit.set_value(&saved.config);
+    let _ = edit.set_attribute("changed", "");
+
+    log::info!("restored plant with seed {}", saved.seed);
+    Some(saved.seed)
+}
+
+fn read_inputs() -> Option<(String, String)> {
+    let document = web_sys::window()?.document()?;
+    let preset = document
+        .get_element_by_id("presets")?
+        .dyn_into::<HtmlSelectElement>()
+        .ok()?
+        .value();
+    let config = document
+        .get_element_by_id("edit-text-input")?
+        .dyn_into::<HtmlTextAreaElement>()
+        .ok()?
+        .value();
+    Some((preset, config))
+}
+
+fn storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use jandering_engine::types::{Vec2, Vec3};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::color_obj::AgeObject;
+
+use super::config::GridConfig;
+
+pub type Cell = (i32, i32);
+
+const ANGLE_JITTER: f32 = 0.15;
+const SIZE_JITTER: f32 = 0.2;
+
+// what makes a cell's plant differ from its neighbours
+pub struct CellVariation {
+    pub seed: u64,
+    pub angle_scale: f32,
+    pub size: f32,
+}
+
+// plants keyed by grid cell, only the window around `center` is kept alive
+pub struct PlantGrid {
+    cells: HashMap<Cell, AgeObject>,
+    // outlives the meshes so a rebuilt plant can keep its seed
+    seeds: HashMap<Cell, u64>,
+    center: Option<Cell>,
+    count: i32,
+    spacing: f32,
+    seed: u64,
+    variation: f32,
+}
+
+impl PlantGrid {
+    pub fn new(config: &GridConfig) -> Self {
+        Self {
+            cells: HashMap::with_capacity((config.count * config.count) as usize),
+            seeds: HashMap::new(),
+            center: None,
+            count: config.count as i32,
+            spacing: config.spacing,
+            seed: config.seed.unwrap_or_else(|| thread_rng().gen()),
+            variation: config.variation,
+        }
+    }
+
+    // a new spacing moves every cell and a new seed or variation changes every plant, so those
+    // rebuild everything, a new count only resizes the window
+    pub fn set_layout(&mut self, config: &GridConfig) {
+        let seed = config.seed.unwrap_or(self.seed);
+        if config.spacing != self.spacing || seed != self.seed || config.variation != self.variation
+        {
+            self.cells.clear();
+            self.seeds.clear();
+            self.spacing = config.spacing;
+            self.seed = seed;
+            self.variation = config.variation;
+        }
+        self.count = config.count as i32;
+        self.center = None;
+    }
+
+    pub fn cell_at(&self, pos: Vec2) -> Cell {
+        let cell = (pos / self.spacing).round();
+        (cell.x as i32, cell.y as i32)
+    }
+
+    pub fn cell_position(&self, (x, z): Cell) -> Vec3 {
+        Vec3::new(x as f32 * self.spacing, 0.0, z as f32 * self.spacing)
+    }
+
+    // moves the window and evicts what fell out of it, returns the cells that still need a plant
+    // closest to the center first
+    pub fn recenter(&mut self, center: Cell) -> Vec<Cell> {
+        if self.center != Some(center) {
+            let count = self.count;
+            self.cells.retain(|cell, _| in_window(*cell, center, count));
+            self.seeds.retain(|cell, _| in_window(*cell, center, count));
+            self.center = Some(center);
+        }
+
+        let mut missing = window(center, self.count)
+            .filter(|cell| !self.cells.contains_key(cell))
+            .collect::<Vec<_>>();
+        missing.sort_by_key(|(x, z)| (x - center.0).pow(2) + (z - center.1).pow(2));
+        missing
+    }
+
+    pub fn insert(&mut self, cell: Cell, seed: u64, plant: AgeObject) {
+        self.cells.insert(cell, plant);
+        self.seeds.insert(cell, seed);
+    }
+
+    // only depends on the base seed and the cell, so a plant scrolling back into view is the same.
+    // a cell takes its own seed, and with it its own rule-set picks, with the variation as the
+    // chance, angles and size get nudged by up to the variation on top
+    pub fn variation(&self, (x, z): Cell) -> CellVariation {
+        let key = (x as u32 as u64) << 32 | z as u32 as u64;
+        let mut rng = StdRng::seed_from_u64(self.seed ^ key);
+        let own_seed = rng.gen::<u64>();
+        let seed = if rng.gen::<f32>() < self.variation {
+            own_seed
+        } else {
+            self.seed
+        };
+        let mut jitter = |range: f32| 1.0 + rng.gen_range(-1.0..=1.0) * range * self.variation;
+        CellVariation {
+            seed,
+            angle_scale: jitter(ANGLE_JITTER),
+            size: jitter(SIZE_JITTER),
+        }
+    }
+
+    pub fn seed(&self, cell: &Cell) -> Option<u64> {
+        self.seeds.get(cell).copied()
+    }
+
+    pub fn get(&self, cell: &Cell) -> Option<&AgeObject> {
+        self.cells.get(cell)
+    }
+
+    pub fn get_mut(&mut self, cell: &Cell) -> Option<&mut AgeObject> {
+        self.cells.get_mut(cell)
+    }
+
+    pub fn center(&self) -> Option<Cell> {
+        self.center
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &Cell> {
+        self.cells.keys()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &AgeObject> {
+        self.cells.values()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.seeds.clear();
+        self.center = None;
+    }
+
+    // drops the meshes but keeps the seeds, so the same plants grow back from a new config
+    pub fn rebuild(&mut self) {
+        self.cells.clear();
+        self.center = None;
+    }
+
+    pub fn nearest(&self, pos: Vec2) -> Option<Cell> {
+        let cell = self.cell_at(pos);
+        if self.cells.contains_key(&cell) {
+            return Some(cell);
+        }
+
+        self.cells
+            .keys()
+            .min_by(|a, b| {
+                let a = self.cell_distance(**a, pos);
+                let b = self.cell_distance(**b, pos);
+                a.total_cmp(&b)
+            })
+            .copied()
+    }
+
+    fn cell_distance(&self, cell: Cell, pos: Vec2) -> f32 {
+        let cell_pos = self.cell_position(cell);
+        Vec2::new(cell_pos.x, cell_pos.z).distance(pos)
+    }
+}
+
+fn window_range(count: i32) -> std::ops::Range<i32> {
+    -(count / 2)..count - count / 2
+}
+
+fn window((cx, cz): Cell, count: i32) -> impl Iterator<Item = Cell> {
+    window_range(count).flat_map(move |x| window_range(count).map(move |z| (cx + x, cz + z)))
+}
+
+fn in_window((x, z): Cell, (cx, cz): Cell, count: i32) -> bool {
+    window_range(count).contains(&(x - cx)) && window_range(count).contains(&(z - cz))
+}
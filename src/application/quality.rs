@@ -0,0 +1,125 @@
+use jandering_engine::core::renderer::Renderer;
+
+use super::{
+    config::{GrassConfig, QualityConfig, SceneConfig},
+    setup::create_grass_instances,
+    Application, N_DUST,
+};
+
+const MAX_LEVEL: usize = 3;
+const GRASS_FRACTION: [f32; MAX_LEVEL + 1] = [1.0, 0.6, 0.35, 0.2];
+const PLANTS_PER_FRAME: [usize; MAX_LEVEL + 1] = [4, 2, 1, 1];
+
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+const DEGRADE_ABOVE: f32 = 1.1;
+const RECOVER_BELOW: f32 = 0.7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstanceBudget {
+    pub grass: u32,
+    pub dust: u32,
+}
+
+impl InstanceBudget {
+    // plants and the leaves falling off them are the scene, so they always get theirs.
+    // grass is cut first and only once it's gone the dust pool shrinks too
+    pub fn new(scene: &SceneConfig) -> Self {
+        let budget = scene.quality.instance_budget;
+        if budget == 0 {
+            return Self {
+                grass: u32::MAX,
+                dust: N_DUST,
+            };
+        }
+
+        let plants = scene.grid.count * scene.grid.count;
+        let left = budget.saturating_sub(plants.saturating_add(scene.leaves.count));
+        let dust = N_DUST.min(left);
+        Self {
+            grass: left - dust,
+            dust,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Quality {
+    level: usize,
+    frame_time: f32,
+    over_budget: f32,
+    under_budget: f32,
+}
+
+impl Quality {
+    pub fn plants_per_frame(&self) -> usize {
+        PLANTS_PER_FRAME[self.level]
+    }
+
+    pub fn grass(&self, config: &GrassConfig) -> GrassConfig {
+        GrassConfig {
+            count: (config.count as f32 * GRASS_FRACTION[self.level]) as u32,
+            ..config.clone()
+        }
+    }
+
+    // returns true when the level changed
+    fn update(&mut self, dt: f32, config: &QualityConfig) -> bool {
+        let budget = config.frame_budget_ms / 1000.0;
+        self.frame_time += (dt - self.frame_time) * FRAME_TIME_SMOOTHING;
+
+        if self.frame_time > budget * DEGRADE_ABOVE {
+            self.over_budget += dt;
+            self.under_budget = 0.0;
+        } else if self.frame_time < budget * RECOVER_BELOW {
+            self.under_budget += dt;
+            self.over_budget = 0.0;
+        } else {
+            self.over_budget = 0.0;
+            self.under_budget = 0.0;
+        }
+
+        let level = if self.over_budget > config.degrade_after_secs {
+            (self.level + 1).min(MAX_LEVEL)
+        } else if self.under_budget > config.recover_after_secs {
+            self.level.saturating_sub(1)
+        } else {
+            self.level
+        };
+
+        if level == self.level {
+            return false;
+        }
+
+        self.level = level;
+        self.over_budget = 0.0;
+        self.under_budget = 0.0;
+        true
+    }
+}
+
+impl Application {
+    pub fn update_quality(&mut self, dt: f32, renderer: &mut dyn Renderer) {
+        let config = &self.scene.quality;
+        let changed = if config.enabled {
+            self.quality.update(dt, config)
+        } else if self.quality.level != 0 {
+            self.quality = Quality::default();
+            true
+        } else {
+            false
+        };
+
+        if changed {
+            log::info!("quality level {}", self.quality.level);
+            self.update_grass_count(renderer);
+        }
+    }
+
+    // a smaller count makes a new vec and update recreates the buffer, so nothing oversized is kept
+    pub fn update_grass_count(&mut self, renderer: &mut dyn Renderer) {
+        let mut grass = self.quality.grass(&self.scene.grass);
+        grass.count = grass.count.min(InstanceBudget::new(&self.scene).grass);
+        self.grass.instances = create_grass_instances(&grass);
+        self.grass.update(renderer);
+    }
+}
@@ -0,0 +1,51 @@
+use super::{logic::save_canvas, Application};
+
+pub struct Recording {
+    frame: u32,
+    frames: u32,
+}
+
+impl Recording {
+    pub fn growth(&self) -> f32 {
+        if self.frames <= 1 {
+            1.0
+        } else {
+            self.frame as f32 / (self.frames - 1) as f32
+        }
+    }
+}
+
+impl Application {
+    pub fn start_recording(&mut self) {
+        if self.recording.is_some() {
+            return;
+        }
+
+        log::info!(
+            "recording {} frames as {}_XXXX.png",
+            self.scene.recording.frames,
+            self.scene.recording.prefix
+        );
+        self.recording = Some(Recording {
+            frame: 0,
+            frames: self.scene.recording.frames.max(1),
+        });
+    }
+
+    pub fn capture_recording_frame(&mut self) {
+        let Some(recording) = &mut self.recording else {
+            return;
+        };
+
+        recording.frame += 1;
+        let file_name = format!("{}_{:04}.png", self.scene.recording.prefix, recording.frame);
+        if save_canvas(&file_name).is_none() {
+            log::warn!("couldn't save {file_name}");
+        }
+
+        if recording.frame >= recording.frames {
+            log::info!("finished recording");
+            self.recording = None;
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::{cell::Cell, collections::HashMap};
+
+use jandering_engine::{
+    core::{
+        engine::Events,
+        object::Instance,
+        renderer::{get_typed_bind_group, Renderer},
+        window::{InputState, MouseButton, WindowEvent},
+    },
+    types::{Mat4, Vec2, Vec3},
+};
+use rand::Rng;
+
+use crate::color_obj::AgeObject;
+
+use super::{logic::camera_ground_intersection, plant_grid, Application};
+
+const SHRINK_SECS: f32 = 0.3;
+const GROW_SECS: f32 = 1.5;
+// a press and release further apart than this is a pan, not a click
+const CLICK_SLOP: f32 = 4.0;
+
+#[derive(Clone, Copy)]
+pub enum RegrowKind {
+    // a new seed, which also picks new rule sets
+    Fresh,
+    // the same seed, just the animation again
+    Replay,
+}
+
+struct Regrow {
+    kind: RegrowKind,
+    elapsed: f32,
+    rebuilt: bool,
+}
+
+#[derive(Default)]
+pub struct Regrowing {
+    regrows: HashMap<plant_grid::Cell, Regrow>,
+    cursor: Option<Vec2>,
+    left_press: Option<Vec2>,
+    right_press: Option<Vec2>,
+}
+
+impl Regrowing {
+    // clicks released this frame, as cursor positions
+    fn clicks(&mut self, events: &Events) -> Vec<(RegrowKind, Vec2)> {
+        let cursor = Cell::new(self.cursor);
+        let (left_press, right_press) = (Cell::new(self.left_press), Cell::new(self.right_press));
+        let clicks = Cell::new(Vec::new());
+        events.matches(|event| {
+            match event {
+                WindowEvent::MouseMotion(position) => cursor.set(Some(Vec2::from(*position))),
+                WindowEvent::MouseLeft => {
+                    cursor.set(None);
+                    left_press.set(None);
+                    right_press.set(None);
+                }
+                WindowEvent::MouseInput { button, state } => {
+                    let (press, kind) = match button {
+                        MouseButton::Left => (&left_press, RegrowKind::Fresh),
+                        MouseButton::Right => (&right_press, RegrowKind::Replay),
+                        _ => return false,
+                    };
+                    if matches!(state, InputState::Pressed) {
+                        press.set(cursor.get());
+                    } else if let (Some(start), Some(end)) = (press.take(), cursor.get()) {
+                        if start.distance(end) <= CLICK_SLOP {
+                            let mut list = clicks.take();
+                            list.push((kind, end));
+                            clicks.set(list);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            false
+        });
+
+        self.cursor = cursor.get();
+        self.left_press = left_press.get();
+        self.right_press = right_press.get();
+        clicks.take()
+    }
+}
+
+impl Application {
+    // left click grows a fresh plant in the clicked cell, right click replays the one that's there,
+    // clicking a plant that's mid regrow does nothing so rapid clicks can't stack
+    pub fn handle_regrow_clicks(&mut self, events: &Events, renderer: &mut dyn Renderer) {
+        for (kind, cursor) in self.regrowing.clicks(events) {
+            if self.gallery.is_some() {
+                continue;
+            }
+            let Some(ground) = self.cursor_ground_position(cursor, renderer) else {
+                continue;
+            };
+            let cell = self.plants.cell_at(Vec2::new(ground.x, ground.z));
+            if self.plants.get(&cell).is_none() || self.regrowing.regrows.contains_key(&cell) {
+                continue;
+            }
+
+            self.regrowing.regrows.insert(
+                cell,
+                Regrow {
+                    kind,
+                    elapsed: 0.0,
+                    rebuilt: false,
+                },
+            );
+        }
+    }
+
+    pub fn update_regrow(&mut self, dt: f32, renderer: &mut dyn Renderer) {
+        let mut regrows = std::mem::take(&mut self.regrowing.regrows);
+        regrows.retain(|cell, regrow| {
+            regrow.elapsed += dt;
+
+            if !regrow.rebuilt && regrow.elapsed >= SHRINK_SECS {
+                regrow.rebuilt = true;
+                let seed = match regrow.kind {
+                    RegrowKind::Fresh => None,
+                    RegrowKind::Replay => self.plants.seed(cell),
+                }
+                .unwrap_or_else(|| self.rng.gen());
+                let variation = self.plants.variation(*cell);
+                let (vertices, indices) = self.new_cell_plant(seed, &variation);
+                let Some(pos) = self
+                    .plants
+                    .get(cell)
+                    .and_then(|plant| plant.instances.first())
+                    .map(|instance| instance.position())
+                else {
+                    return false;
+                };
+                let object = AgeObject::new(
+                    renderer,
+                    vertices,
+                    indices,
+                    vec![Instance::default().translate(pos)],
+                );
+                self.plants.insert(*cell, seed, object);
+                self.lod.forget(cell);
+            }
+
+            // the window may have moved on and taken the plant with it
+            let Some(plant) = self.plants.get_mut(cell) else {
+                return false;
+            };
+
+            let (scale, done) = if regrow.rebuilt {
+                let t = ((regrow.elapsed - SHRINK_SECS) / GROW_SECS).min(1.0);
+                (smoothstep(t), t >= 1.0)
+            } else {
+                (1.0 - smoothstep(regrow.elapsed / SHRINK_SECS), false)
+            };
+
+            let instance = plant.instances.first_mut().unwrap();
+            let (_, rotation, pos) = instance.mat().to_scale_rotation_translation();
+            instance.set_mat(Mat4::from_scale_rotation_translation(
+                Vec3::splat(scale),
+                rotation,
+                pos,
+            ));
+            plant.update(renderer);
+
+            !done
+        });
+        self.regrowing.regrows = regrows;
+    }
+
+    // where the cursor lands on the ground, this assumes the orthographic camera so the ray just
+    // starts off to the side of the camera instead of fanning out
+    fn cursor_ground_position(&self, cursor: Vec2, renderer: &dyn Renderer) -> Option<Vec3> {
+        let size = renderer.size();
+        let size = Vec2::new(size.x as f32, size.y as f32);
+        let ndc = Vec2::new(cursor.x / size.x * 2.0 - 1.0, 1.0 - cursor.y / size.y * 2.0);
+
+        let camera = get_typed_bind_group(renderer, self.camera).unwrap();
+        let direction = camera.direction();
+        let right = direction.cross(Vec3::Y).normalize();
+        let up = right.cross(direction);
+
+        let height = self.scene.camera.ortho_width;
+        let width = height * size.x / size.y;
+        let origin = camera.position() + right * ndc.x * width / 2.0 + up * ndc.y * height / 2.0;
+        camera_ground_intersection(direction, origin)
+    }
+}
+
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,46 @@
+use jandering_engine::types::Vec3;
+
+use crate::l_system::{colors, RenderConfig};
+
+use super::config::SeasonsConfig;
+
+// replaces the page gradient with a blend of the two seasons around `time`,
+// names that aren't luts in the loaded config are skipped
+pub fn season_lut(
+    config: &SeasonsConfig,
+    rendering: &RenderConfig,
+    time: f32,
+    resolution: u32,
+) -> Option<Vec<Vec3>> {
+    if !config.enabled {
+        return None;
+    }
+
+    let luts = config
+        .luts
+        .iter()
+        .filter_map(|name| rendering.named_lut(name))
+        .map(|lut| colors::resample(&lut, resolution))
+        .collect::<Vec<_>>();
+    if luts.is_empty() {
+        return None;
+    }
+
+    let n = luts.len();
+    let phase = (time / config.period_secs.max(f32::EPSILON)).fract() * n as f32;
+    let current = (phase as usize).min(n - 1);
+    let next = (current + 1) % n;
+    let t = smoothstep(phase.fract());
+
+    Some(
+        luts[current]
+            .iter()
+            .zip(&luts[next])
+            .map(|(a, b)| a.lerp(*b, t))
+            .collect(),
+    )
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use image::GenericImageView;
 use jandering_engine::{
     core::{
@@ -7,7 +5,7 @@ use jandering_engine::{
             camera::free::{CameraController, MatrixCameraBindGroup},
             texture::TextureBindGroup,
         },
-        object::{Instance, Object, Vertex},
+        object::{primitives::quad_data, Instance},
         renderer::{
             create_typed_bind_group, create_typed_bind_group_at, get_typed_bind_group,
             BindGroupHandle, Renderer, SamplerHandle, ShaderHandle, TextureHandle,
@@ -22,71 +20,165 @@ use jandering_engine::{
 use crate::{
     camera_controller::IsometricCameraController,
     color_obj::{AgeObject, AgeVertex},
+    grass_blade,
     image::Image,
+    l_system::colors,
+    plane,
 };
 
 use super::{
-    logic::read_lut, Plants, RenderDataBindGroup, N_DUST, N_GRASS, ORTHO_FAR, ORTHO_HEIGHT,
-    ORTHO_NEAR, ORTHO_WIDTH, REFERENCE_DIAGONAL,
+    config::{CameraConfig, DustConfig, FloorConfig, GrassConfig, GridConfig},
+    logic::read_lut,
+    plant_grid::PlantGrid,
+    shaders::ShaderRegistry,
+    RenderDataBindGroup, N_DUST, ORTHO_FAR, ORTHO_NEAR,
 };
 
-pub fn create_camera(renderer: &mut dyn Renderer) -> BindGroupHandle<MatrixCameraBindGroup> {
-    let (aspect, diagonal) = {
+const SHADER_PATH: &str = "shaders/shader.wgsl";
+
+pub fn create_camera(
+    renderer: &mut dyn Renderer,
+    config: &CameraConfig,
+) -> BindGroupHandle<MatrixCameraBindGroup> {
+    let aspect = {
         let size = renderer.size();
-        let size = Vec2::new(size.x as f32, size.y as f32);
-        (size.x / size.y, (size.x * size.x + size.y * size.y).sqrt())
-    };
-    let controller = IsometricCameraController {
-        pan_speed: 0.002 * (diagonal / REFERENCE_DIAGONAL),
-        ..Default::default()
+        size.x as f32 / size.y as f32
     };
-    let controller: Box<dyn CameraController> = Box::new(controller);
+    let controller = create_camera_controller(renderer, config);
     let mut camera = MatrixCameraBindGroup::with_controller(controller);
+    apply_camera_pose(&mut camera, config, aspect);
+    create_typed_bind_group(renderer, camera)
+}
+
+// pan speed follows the screen diagonal so a drag covers the same part of the view everywhere
+pub fn create_camera_controller(
+    renderer: &dyn Renderer,
+    config: &CameraConfig,
+) -> Box<dyn CameraController> {
+    let diagonal = {
+        let size = renderer.size();
+        Vec2::new(size.x as f32, size.y as f32).length()
+    };
+    Box::new(IsometricCameraController {
+        pan_speed: config.pan_speed * (diagonal / config.reference_diagonal),
+        ..Default::default()
+    })
+}
+
+pub fn apply_camera_pose(camera: &mut MatrixCameraBindGroup, config: &CameraConfig, aspect: f32) {
+    let width = config.ortho_width;
+    let height = width;
     camera.make_ortho(
-        (-ORTHO_WIDTH * aspect) / 2.0,
-        (ORTHO_WIDTH * aspect) / 2.0,
-        -ORTHO_HEIGHT / 2.0,
-        ORTHO_HEIGHT / 2.0,
+        (-width * aspect) / 2.0,
+        (width * aspect) / 2.0,
+        -height / 2.0,
+        height / 2.0,
         ORTHO_NEAR,
         ORTHO_FAR,
     );
-    *camera.position_mut() = Vec3::new(-9.5, 10.0, -9.5);
-    *camera.direction_mut() = Vec3::new(1.0, -1.0, 1.0).normalize();
-    create_typed_bind_group(renderer, camera)
+    *camera.position_mut() = Vec3::from(config.position);
+    *camera.direction_mut() = Vec3::from(config.direction);
 }
 
 pub fn create_objects(
     renderer: &mut dyn Renderer,
-) -> (Plants, Object<Instance>, AgeObject, AgeObject) {
-    let floor = Object::quad(
-        renderer,
-        vec![Instance::default()
-            .rotate(90.0f32.to_radians(), Vec3::X)
-            .set_size(Vec3::splat(100.0))],
-    );
+    grass_config: &GrassConfig,
+    grid_config: &GridConfig,
+    floor_config: &FloorConfig,
+    dust_config: &DustConfig,
+    heightmap: &Image,
+) -> (PlantGrid, AgeObject, AgeObject, AgeObject, AgeObject) {
+    let floor = create_floor(renderer, floor_config, heightmap);
+
+    let plants = PlantGrid::new(grid_config);
+
+    let dust = create_dust(renderer, dust_config, N_DUST);
 
-    let mut plants = HashMap::new();
-    plants.reserve(50);
+    let grass = create_grass(renderer, grass_config);
 
-    let dust_instances = (0..N_DUST)
+    let sky = create_sky(renderer);
+
+    (plants, floor, dust, grass, sky)
+}
+
+pub fn create_floor(
+    renderer: &mut dyn Renderer,
+    config: &FloorConfig,
+    heightmap: &Image,
+) -> AgeObject {
+    let (vertices, indices) =
+        plane::displaced_plane(config.resolution, config.size, heightmap, config.height);
+    AgeObject::new(renderer, vertices, indices, vec![Instance::default()])
+}
+
+// a single flat quad as big as the floor, its height comes from the instance
+pub fn create_water(
+    renderer: &mut dyn Renderer,
+    config: &FloorConfig,
+    heightmap: &Image,
+) -> AgeObject {
+    let (vertices, indices) = plane::displaced_plane(1, config.size, heightmap, 0.0);
+    AgeObject::new(renderer, vertices, indices, vec![Instance::default()])
+}
+
+// covers the whole screen in clip space, vs_sky doesn't transform it
+fn create_sky(renderer: &mut dyn Renderer) -> AgeObject {
+    let vertices = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .into_iter()
+        .map(|(x, y)| AgeVertex {
+            position: Vec3::new(x, y, 0.0),
+            ..Default::default()
+        })
+        .collect();
+    AgeObject::new(
+        renderer,
+        vertices,
+        vec![0, 1, 2, 0, 2, 3],
+        vec![Instance::default()],
+    )
+}
+
+pub fn create_dust(renderer: &mut dyn Renderer, config: &DustConfig, count: u32) -> AgeObject {
+    let (vertices, indices) = quad_data();
+    let vertices = vertices
+        .into_iter()
+        .map(|e| AgeVertex {
+            age: 0.3,
+            color: config.color.map(Vec3::from).unwrap_or_default(),
+            color_weight: if config.color.is_some() { 1.0 } else { 0.0 },
+            ..AgeVertex::from(e)
+        })
+        .collect();
+    let instances = (0..count)
         .map(|_| Instance::default().translate(Vec3::splat(-1000.0)))
         .collect();
-    let dust = AgeObject::quad(renderer, 0.3, dust_instances);
 
-    let grass_instances = (0..N_GRASS)
+    AgeObject::new(renderer, vertices, indices, instances)
+}
+
+pub fn create_grass(renderer: &mut dyn Renderer, config: &GrassConfig) -> AgeObject {
+    let instances = create_grass_instances(config);
+    if config.blade_segments == 0 {
+        return AgeObject::quad(renderer, 1.0, instances);
+    }
+
+    let (vertices, indices) = grass_blade::generate(config.blade_segments, 1.0);
+    AgeObject::new(renderer, vertices, indices, instances)
+}
+
+pub fn create_grass_instances(config: &GrassConfig) -> Vec<Instance> {
+    (0..config.count)
         .map(|_| {
             Instance::default()
-                .set_size(Vec3::new(0.008, 0.1, 1.0))
+                .set_size(Vec3::new(config.width, config.height, 1.0))
                 .set_position(Vec3::new(1000.0, 0.0, 0.0))
         })
-        .collect::<Vec<_>>();
-    let grass = AgeObject::quad(renderer, 1.0, grass_instances);
-
-    (plants, floor, dust, grass)
+        .collect()
 }
 
 pub async fn create_textures(
     renderer: &mut dyn Renderer,
+    lut_resolution: u32,
 ) -> (
     TextureHandle,
     Image,
@@ -96,7 +188,7 @@ pub async fn create_textures(
     BindGroupHandle<TextureBindGroup>,
 ) {
     let (lut_texture, lut_texture_linear, lut_sampler) =
-        create_lut_textures(renderer, None, None, None);
+        create_lut_textures(renderer, lut_resolution, None, &[], None, None, None);
     let depth_texture = renderer.create_texture(TextureDescriptor {
         size: renderer.size(),
         format: TextureFormat::Depth32F,
@@ -131,14 +223,22 @@ pub async fn create_textures(
 
 pub async fn create_shaders(
     renderer: &mut dyn Renderer,
-) -> (ShaderHandle, ShaderHandle, ShaderHandle, ShaderHandle) {
+) -> (
+    ShaderRegistry,
+    ShaderHandle,
+    ShaderHandle,
+    ShaderHandle,
+    ShaderHandle,
+    ShaderHandle,
+    ShaderHandle,
+    ShaderHandle,
+    ShaderHandle,
+) {
     let descriptor = ShaderDescriptor::default()
         .with_source(jandering_engine::core::shader::ShaderSource::Code(
-            load_text(jandering_engine::utils::FilePath::FileName(
-                "shaders/shader.wgsl",
-            ))
-            .await
-            .unwrap(),
+            load_text(jandering_engine::utils::FilePath::FileName(SHADER_PATH))
+                .await
+                .unwrap(),
         ))
         .with_descriptors(vec![AgeVertex::desc(), Instance::desc()])
         .with_depth(true)
@@ -149,24 +249,72 @@ pub async fn create_shaders(
             TextureBindGroup::get_layout(),
             TextureBindGroup::get_layout(),
         ]);
-    let shader: ShaderHandle =
-        renderer.create_shader(descriptor.clone().with_fs_entry("fs_color_object"));
-    let floor_shader: ShaderHandle = renderer.create_shader(
+    let mut shaders = ShaderRegistry::default();
+    let shader = shaders.create(
+        renderer,
+        SHADER_PATH,
+        descriptor.clone().with_fs_entry("fs_color_object"),
+    );
+    let floor_shader = shaders.create(
+        renderer,
+        SHADER_PATH,
+        descriptor.clone().with_fs_entry("fs_floor"),
+    );
+    let grass_shader = shaders.create(
+        renderer,
+        SHADER_PATH,
+        descriptor.clone().with_fs_entry("fs_grass"),
+    );
+    let dust_shader = shaders.create(
+        renderer,
+        SHADER_PATH,
+        descriptor.clone().with_fs_entry("fs_dust"),
+    );
+    let leaf_shader = shaders.create(
+        renderer,
+        SHADER_PATH,
+        descriptor.clone().with_fs_entry("fs_leaf"),
+    );
+    let sky_shader = shaders.create(
+        renderer,
+        SHADER_PATH,
         descriptor
             .clone()
-            .with_descriptors(vec![Vertex::desc(), Instance::desc()])
-            .with_fs_entry("fs_floor"),
+            .with_vs_entry("vs_sky")
+            .with_fs_entry("fs_sky"),
+    );
+    let outline_shader = shaders.create(
+        renderer,
+        SHADER_PATH,
+        descriptor
+            .clone()
+            .with_vs_entry("vs_outline")
+            .with_fs_entry("fs_outline"),
+    );
+    let water_shader = shaders.create(
+        renderer,
+        SHADER_PATH,
+        descriptor.clone().with_fs_entry("fs_water"),
     );
-    let grass_shader: ShaderHandle =
-        renderer.create_shader(descriptor.clone().with_fs_entry("fs_grass"));
-    let dust_shader: ShaderHandle =
-        renderer.create_shader(descriptor.clone().with_fs_entry("fs_dust"));
 
-    (shader, floor_shader, grass_shader, dust_shader)
+    (
+        shaders,
+        shader,
+        floor_shader,
+        grass_shader,
+        dust_shader,
+        leaf_shader,
+        sky_shader,
+        outline_shader,
+        water_shader,
+    )
 }
 
 pub fn create_lut_textures(
     renderer: &mut dyn Renderer,
+    resolution: u32,
+    base: Option<Vec<Vec3>>,
+    luts: &[Vec<Vec3>],
     lut_handle: Option<BindGroupHandle<TextureBindGroup>>,
     lut_handle_linear: Option<BindGroupHandle<TextureBindGroup>>,
     mut lut_sampler: Option<SamplerHandle>,
@@ -182,10 +330,19 @@ pub fn create_lut_textures(
         }));
     }
 
-    let data = read_lut(false)
-        .unwrap_or_default()
+    let resolution = resolution.min(renderer.max_texture_size().x);
+    let base = base.unwrap_or_else(|| read_lut(false).unwrap_or_default());
+    let rows = std::iter::once(base)
+        .chain(luts.iter().cloned())
+        .take(renderer.max_texture_size().y as usize)
+        .collect::<Vec<_>>();
+    let data = rows
         .iter()
-        .take(renderer.max_texture_size().x as usize)
+        .flat_map(|e| {
+            let mut row = colors::resample(e, resolution);
+            row.resize(resolution as usize, Vec3::ZERO);
+            row
+        })
         .flat_map(|e| {
             [
                 (e.x * 255.0) as u8,
@@ -198,8 +355,12 @@ pub fn create_lut_textures(
     let mut desc = TextureDescriptor {
         data: if data.is_empty() { None } else { Some(&data) },
         size: UVec2 {
-            x: (data.len() as u32 / 4).max(1),
-            y: 1,
+            x: if data.is_empty() { 1 } else { resolution },
+            y: if data.is_empty() {
+                1
+            } else {
+                rows.len() as u32
+            },
         },
         format: TextureFormat::Rgba8U,
         ..Default::default()
@@ -235,7 +396,10 @@ pub fn create_lut_textures(
         .collect::<Vec<_>>();
 
     desc.data = if data.is_empty() { None } else { Some(&data) };
-    desc.size.x = (data.len() as u32 / 4).max(1);
+    desc.size = UVec2 {
+        x: (data.len() as u32 / 4).max(1),
+        y: 1,
+    };
 
     let lut_texture_linear = if let Some(handle) = lut_handle_linear {
         let texture_handle = get_typed_bind_group(renderer, handle)
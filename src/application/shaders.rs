@@ -0,0 +1,79 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use jandering_engine::{
+    core::{
+        renderer::{Renderer, ShaderHandle},
+        shader::{ShaderDescriptor, ShaderSource},
+    },
+    utils::{load_text, FilePath},
+};
+
+struct RegisteredShader {
+    handle: ShaderHandle,
+    path: &'static str,
+    descriptor: ShaderDescriptor,
+}
+
+// remembers how every shader was created so any of them can be rebuilt from fresh source
+pub struct ShaderRegistry {
+    shaders: Vec<RegisteredShader>,
+    sender: Sender<(&'static str, String)>,
+    receiver: Receiver<(&'static str, String)>,
+}
+
+impl Default for ShaderRegistry {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            shaders: Vec::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl ShaderRegistry {
+    pub fn create(
+        &mut self,
+        renderer: &mut dyn Renderer,
+        path: &'static str,
+        descriptor: ShaderDescriptor,
+    ) -> ShaderHandle {
+        let handle = renderer.create_shader(descriptor.clone());
+        self.shaders.push(RegisteredShader {
+            handle,
+            path,
+            descriptor,
+        });
+        handle
+    }
+
+    pub fn reload(&self) {
+        let mut paths = self.shaders.iter().map(|e| e.path).collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+
+        for path in paths {
+            let sender = self.sender.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let text = load_text(FilePath::FileName(path)).await.unwrap();
+                let _ = sender.send((path, text));
+            });
+        }
+    }
+
+    // only the shaders created from a reloaded path are rebuilt, each with its own descriptor
+    pub fn update(&self, renderer: &mut dyn Renderer) {
+        for (path, code) in self.receiver.try_iter() {
+            for shader in self.shaders.iter().filter(|e| e.path == path) {
+                renderer.create_shader_at(
+                    shader
+                        .descriptor
+                        .clone()
+                        .with_source(ShaderSource::Code(code.clone())),
+                    shader.handle,
+                );
+            }
+        }
+    }
+}
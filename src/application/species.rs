@@ -0,0 +1,63 @@
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlSelectElement, HtmlTextAreaElement};
+
+use crate::l_system::config::LConfig;
+
+use super::Application;
+
+pub struct Species {
+    pub name: String,
+    text: String,
+    config: LConfig,
+}
+
+// parsed once up front so switching is just a clone, files that don't parse are left out
+pub fn parse_species(presets: &[(String, String)]) -> Vec<Species> {
+    presets
+        .iter()
+        .filter_map(|(name, text)| match LConfig::from_json(text.clone()) {
+            Ok(config) => Some(Species {
+                name: name.clone(),
+                text: text.clone(),
+                config,
+            }),
+            Err(error) => {
+                log::warn!("skipping species '{name}': {error}");
+                None
+            }
+        })
+        .collect()
+}
+
+impl Application {
+    // the plants keep their seeds and the growth carries on, only the grammar changes
+    pub fn next_species(&mut self) {
+        if self.species.is_empty() {
+            return;
+        }
+
+        self.species_index = (self.species_index + 1) % self.species.len();
+        let species = &self.species[self.species_index];
+        log::info!("species: {}", species.name);
+
+        self.l_config = species.config.clone();
+        self.plants.rebuild();
+        show_species(species);
+    }
+}
+
+// mirrors the switch in the preset picker without marking it changed, so nothing gets parsed again
+fn show_species(species: &Species) -> Option<()> {
+    let document = web_sys::window()?.document()?;
+    document
+        .get_element_by_id("presets")?
+        .dyn_into::<HtmlSelectElement>()
+        .ok()?
+        .set_value(&species.name);
+    document
+        .get_element_by_id("edit-text-input")?
+        .dyn_into::<HtmlTextAreaElement>()
+        .ok()?
+        .set_value(&species.text);
+    Some(())
+}
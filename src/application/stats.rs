@@ -0,0 +1,71 @@
+use wasm_bindgen::JsCast;
+use web_sys::HtmlDivElement;
+
+use crate::color_obj::MeshStats;
+
+use super::Application;
+
+const FRAME_TIME_SMOOTHING: f32 = 0.05;
+
+#[derive(Default)]
+pub struct Stats {
+    pub visible: bool,
+    frame_time: f32,
+}
+
+impl Application {
+    pub fn toggle_stats(&mut self) {
+        self.stats.visible = !self.stats.visible;
+        set_stats_visible(self.stats.visible);
+    }
+
+    pub fn update_stats(&mut self, dt: f32) {
+        self.stats.frame_time += (dt - self.stats.frame_time) * FRAME_TIME_SMOOTHING;
+        if !self.stats.visible {
+            return;
+        }
+
+        let mesh = if let Some(gallery) = &self.gallery {
+            gallery
+                .plants
+                .iter()
+                .map(|e| e.object.mesh_stats())
+                .fold(MeshStats::default(), |acc, e| acc + e)
+        } else {
+            self.plants
+                .values()
+                .map(|e| e.mesh_stats())
+                .fold(MeshStats::default(), |acc, e| acc + e)
+        };
+
+        let text = format!(
+            "frame time: {:.2} ms ({:.0} fps)\nvertices: {}\ntriangles: {}\niterations: {}\ngrowth: {:.2}",
+            self.stats.frame_time * 1000.0,
+            1.0 / self.stats.frame_time.max(f32::EPSILON),
+            mesh.vertices,
+            mesh.triangles,
+            self.l_config.rules.iterations,
+            self.growth,
+        );
+        set_stats_text(&text);
+    }
+}
+
+fn stats_element() -> Option<HtmlDivElement> {
+    web_sys::window()?
+        .document()?
+        .get_element_by_id("stats")?
+        .dyn_into::<HtmlDivElement>()
+        .ok()
+}
+
+fn set_stats_visible(visible: bool) -> Option<()> {
+    let class = if visible { "gui" } else { "gui hidden" };
+    stats_element()?.set_class_name(class);
+    Some(())
+}
+
+fn set_stats_text(text: &str) -> Option<()> {
+    stats_element()?.set_inner_text(text);
+    Some(())
+}
@@ -0,0 +1,75 @@
+use jandering_engine::core::renderer::{get_typed_bind_group_mut, Renderer};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlDivElement, HtmlInputElement};
+
+use super::Application;
+
+#[derive(Default)]
+pub struct Tweaks {
+    pub visible: bool,
+}
+
+impl Application {
+    pub fn toggle_tweaks(&mut self) {
+        self.tweaks.visible = !self.tweaks.visible;
+        set_tweaks_visible(self.tweaks.visible);
+    }
+
+    // sliders write straight into the live state, so nothing is read while the panel is closed
+    pub fn apply_tweaks(&mut self, renderer: &mut dyn Renderer) {
+        if !self.tweaks.visible {
+            return;
+        }
+
+        let render_data = get_typed_bind_group_mut(renderer, self.render_data).unwrap();
+        let data = &mut render_data.data;
+        for (id, value) in [
+            ("tweak-wind-scale", &mut data.wind_scale),
+            ("tweak-wind-speed", &mut data.wind_speed),
+            ("tweak-wind-direction", &mut data.wind_direction),
+            ("tweak-wind-noise-scale", &mut data.wind_noise_scale),
+            ("tweak-wind-noise-strength", &mut data.wind_noise_strength),
+        ] {
+            if let Some(slider) = read_slider(id) {
+                *value = slider;
+            }
+        }
+
+        if let Some(count) = read_slider("tweak-grass-count") {
+            let count = count as u32;
+            if count != self.scene.grass.count {
+                self.scene.grass.count = count;
+                self.update_grass_count(renderer);
+            }
+        }
+
+        if let Some(scale) = read_slider("tweak-angle-scale") {
+            if scale != self.l_config.rendering.angle_scale {
+                self.l_config.rendering.angle_scale = scale;
+                self.plants.clear();
+            }
+        }
+    }
+}
+
+fn read_slider(id: &str) -> Option<f32> {
+    web_sys::window()?
+        .document()?
+        .get_element_by_id(id)?
+        .dyn_into::<HtmlInputElement>()
+        .ok()?
+        .value()
+        .parse::<f32>()
+        .ok()
+}
+
+fn set_tweaks_visible(visible: bool) -> Option<()> {
+    let class = if visible { "gui" } else { "gui hidden" };
+    web_sys::window()?
+        .document()?
+        .get_element_by_id("tweaks")?
+        .dyn_into::<HtmlDivElement>()
+        .ok()?
+        .set_class_name(class);
+    Some(())
+}
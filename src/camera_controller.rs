@@ -6,6 +6,8 @@ use jandering_engine::{
     types::{Vec2, Vec3},
 };
 const CAMERA_SPEED: f32 = 20.0;
+// per second, matches the old `velocity * (1 - 6 * dt)` step at 30 fps
+const VELOCITY_DAMPING: f32 = 6.7;
 
 pub struct IsometricCameraController {
     pub pan_speed: f32,
@@ -143,6 +145,6 @@ impl CameraController for IsometricCameraController {
         *object_position += -pan_delta.x * right + self.velocity.x * right * dt;
         *object_position += pan_delta.y * dir + self.velocity.y * dir * dt;
 
-        self.velocity += -self.velocity * (dt * 6.0);
+        self.velocity *= (-VELOCITY_DAMPING * dt).exp();
     }
 }
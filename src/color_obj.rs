@@ -6,7 +6,7 @@ use jandering_engine::{
             BufferLayout, BufferLayoutEntry, BufferLayoutEntryDataType, BufferLayoutStepMode,
         },
     },
-    types::Vec3,
+    types::{Vec2, Vec3},
 };
 
 #[repr(C)]
@@ -16,6 +16,14 @@ pub struct AgeVertex {
     pub position_padding: f32,
     pub normal: Vec3,
     pub age: f32,
+    pub color: Vec3,
+    pub color_weight: f32,
+    pub lut: f32,
+    pub base_height: f32,
+    pub metallic: f32,
+    pub roughness: f32,
+    // u goes around a tube and v along it, spheres are wrapped by longitude and latitude
+    pub uv: Vec2,
 }
 
 impl AgeVertex {
@@ -35,11 +43,86 @@ impl AgeVertex {
                     location: 2,
                     data_type: BufferLayoutEntryDataType::Float32,
                 },
+                BufferLayoutEntry {
+                    location: 3,
+                    data_type: BufferLayoutEntryDataType::Float32x3,
+                },
+                BufferLayoutEntry {
+                    location: 4,
+                    data_type: BufferLayoutEntryDataType::Float32,
+                },
+                // lut and base height share one attribute, as do metallic and roughness. together
+                // with the instance matrices this is exactly the 16 attributes wgpu allows
+                BufferLayoutEntry {
+                    location: 13,
+                    data_type: BufferLayoutEntryDataType::Float32x2,
+                },
+                BufferLayoutEntry {
+                    location: 14,
+                    data_type: BufferLayoutEntryDataType::Float32x2,
+                },
+                BufferLayoutEntry {
+                    location: 15,
+                    data_type: BufferLayoutEntryDataType::Float32x2,
+                },
             ],
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MeshStats {
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+impl MeshStats {
+    pub fn new(vertices: &[AgeVertex], indices: &[u32]) -> Self {
+        Self {
+            vertices: vertices.len(),
+            triangles: indices.len() / 3,
+        }
+    }
+}
+
+impl std::ops::Add for MeshStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            vertices: self.vertices + rhs.vertices,
+            triangles: self.triangles + rhs.triangles,
+        }
+    }
+}
+
+impl std::fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "V={} T={}", self.vertices, self.triangles)
+    }
+}
+
+// what generating one plant cost, build is the l-system itself and mesh is turning its shapes
+// into vertices
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlantStats {
+    pub build_ms: f32,
+    pub mesh_ms: f32,
+    pub shapes: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+impl std::fmt::Display for PlantStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "build {:.2}ms mesh {:.2}ms shapes={} V={} T={}",
+            self.build_ms, self.mesh_ms, self.shapes, self.vertices, self.triangles
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct AgeObject {
     pub vertices: Vec<AgeVertex>,
@@ -95,6 +178,20 @@ impl AgeObject {
         }
     }
 
+    pub fn mesh_stats(&self) -> MeshStats {
+        MeshStats::new(&self.vertices, &self.indices)
+    }
+
+    pub fn bounds(&self, max_age: f32) -> Option<(Vec3, Vec3)> {
+        self.vertices
+            .iter()
+            .filter(|e| e.age <= max_age)
+            .fold(None, |acc, e| match acc {
+                Some((min, max)) => Some((e.position.min(min), e.position.max(max))),
+                None => Some((e.position, e.position)),
+            })
+    }
+
     pub fn quad(renderer: &mut dyn Renderer, age: f32, instances: Vec<Instance>) -> Self {
         let (vertices, indices) = quad_data();
         let vertices = vertices
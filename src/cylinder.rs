@@ -1,33 +1,38 @@
-use jandering_engine::{core::object::Vertex, types::Vec3};
+use jandering_engine::types::{Vec2, Vec3};
 
-pub fn generate(resolution: u32) -> (Vec<Vertex>, Vec<u32>) {
+use crate::color_obj::AgeVertex;
+
+// the first column is repeated at the end with u = 1, so the texture doesn't run backwards
+// across the last face
+pub fn generate(resolution: u32) -> (Vec<AgeVertex>, Vec<u32>) {
     let mut vertices = Vec::new();
 
-    (0..resolution).for_each(|i| {
+    (0..=resolution).for_each(|i| {
         let ratio = i as f32 / resolution as f32;
         let r = ratio * std::f32::consts::PI * 2.0;
         let x = r.cos();
         let z = r.sin();
         let normal = Vec3::new(x, 0.0, z).normalize();
-        vertices.push(Vertex {
+        vertices.push(AgeVertex {
             position: Vec3::new(x, -0.5, z),
             normal,
+            uv: Vec2::new(ratio, 0.0),
             ..Default::default()
         });
-        vertices.push(Vertex {
+        vertices.push(AgeVertex {
             position: Vec3::new(x, 0.5, z),
             normal,
+            uv: Vec2::new(ratio, 1.0),
             ..Default::default()
         });
     });
 
     let mut indices = Vec::new();
-    let resolution_2 = resolution * 2;
     (0..resolution).for_each(|mut i| {
         i *= 2;
         let j = i + 1;
-        let k = (i + 2) % resolution_2;
-        let l = (i + 3) % resolution_2;
+        let k = i + 2;
+        let l = i + 3;
         indices.push(i);
         indices.push(j);
         indices.push(k);
@@ -0,0 +1,37 @@
+use jandering_engine::types::Vec3;
+
+use crate::color_obj::AgeVertex;
+
+const CURVE: f32 = 0.15;
+
+// a unit blade from y 0 to 1 narrowing to a point, width and height come from the instance scale
+pub fn generate(segments: u32, age: f32) -> (Vec<AgeVertex>, Vec<u32>) {
+    let segments = segments.max(1);
+
+    let vertex = |x: f32, t: f32| AgeVertex {
+        position: Vec3::new(x, t, t * t * CURVE),
+        normal: Vec3::new(0.0, -2.0 * t * CURVE, 1.0).normalize(),
+        age,
+        ..Default::default()
+    };
+
+    let mut vertices = (0..segments)
+        .flat_map(|i| {
+            let t = i as f32 / segments as f32;
+            let half_width = 0.5 * (1.0 - t);
+            [vertex(-half_width, t), vertex(half_width, t)]
+        })
+        .collect::<Vec<_>>();
+    vertices.push(vertex(0.0, 1.0));
+
+    let mut indices = (0..segments - 1)
+        .flat_map(|i| {
+            let i = i * 2;
+            [i, i + 1, i + 2, i + 1, i + 3, i + 2]
+        })
+        .collect::<Vec<_>>();
+    let last = (segments - 1) * 2;
+    indices.extend([last, last + 1, last + 2]);
+
+    (vertices, indices)
+}
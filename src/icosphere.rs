@@ -1,9 +1,14 @@
-use jandering_engine::types::Mat4;
+use std::collections::HashMap;
+
+use jandering_engine::types::{Mat4, Vec2, Vec3};
 
 use crate::color_obj::AgeVertex;
 
 type Triangle = [u32; 3];
 
+// every level has four times the triangles, past this a berry outweighs the branch it hangs on
+const MAX_SUBDIVISIONS: u32 = 4;
+
 mod icosahedron {
     use jandering_engine::types::Vec3;
 
@@ -52,17 +57,68 @@ mod icosahedron {
     ];
 }
 
-pub fn generate(age: f32, mat: Mat4, index_offset: u32) -> (Vec<AgeVertex>, Vec<u32>) {
-    let vertices = icosahedron::VERTICES
+// splits every triangle into four and pushes the new midpoints out onto the sphere,
+// edges are shared between two triangles so their midpoints are cached by the sorted pair
+fn subdivide(subdivisions: u32) -> (Vec<Vec3>, Vec<Triangle>) {
+    let mut vertices = icosahedron::VERTICES.to_vec();
+    let mut triangles = icosahedron::TRIANGLES.to_vec();
+
+    for _ in 0..subdivisions.min(MAX_SUBDIVISIONS) {
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let point = (vertices[a as usize] + vertices[b as usize]).normalize();
+                vertices.push(point);
+                vertices.len() as u32 - 1
+            })
+        };
+
+        triangles = triangles
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    (vertices, triangles)
+}
+
+// longitude around y and latitude from the top, the vertices are shared so triangles across
+// the date line stretch over the whole texture
+fn sphere_uv(v: Vec3) -> Vec2 {
+    Vec2::new(
+        v.z.atan2(v.x) / std::f32::consts::TAU + 0.5,
+        v.y.clamp(-1.0, 1.0).acos() / std::f32::consts::PI,
+    )
+}
+
+// ages run from `age` on the side facing back along `heading` to `next_age` on the far side,
+// the same way a cylinder goes from its base to its tip
+pub fn generate(
+    age: f32,
+    next_age: f32,
+    heading: Vec3,
+    subdivisions: u32,
+    color: Option<Vec3>,
+    mat: Mat4,
+    index_offset: u32,
+) -> (Vec<AgeVertex>, Vec<u32>) {
+    let (points, triangles) = subdivide(subdivisions);
+    let vertices = points
         .iter()
         .map(|v| AgeVertex {
             position: mat.transform_vector3(*v),
             normal: v.normalize(),
-            age,
+            age: age + (next_age - age) * (v.normalize().dot(heading) * 0.5 + 0.5),
+            color: color.unwrap_or_default(),
+            color_weight: if color.is_some() { 1.0 } else { 0.0 },
+            uv: sphere_uv(v.normalize()),
             ..Default::default()
         })
         .collect();
-    let indices = icosahedron::TRIANGLES
+    let indices = triangles
         .iter()
         .flatten()
         .map(|e| *e + index_offset)
@@ -1,17 +1,57 @@
 use std::ops::{Add, Rem, Sub};
 
 use image::Rgb32FImage;
-use jandering_engine::types::Vec2;
+use jandering_engine::types::{Vec2, Vec3};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressMode {
+    Repeat,
+    Clamp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterMode {
+    Nearest,
+    Smooth,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sampling {
+    pub address: AddressMode,
+    pub filter: FilterMode,
+}
+
+impl Sampling {
+    // what the terrain has always used, a soft heightmap that tiles forever
+    pub const TILING: Self = Self {
+        address: AddressMode::Repeat,
+        filter: FilterMode::Smooth,
+    };
+
+    // exact texels that stop at the edge, for masks and lookup tables
+    #[allow(dead_code)]
+    pub const LOOKUP: Self = Self {
+        address: AddressMode::Clamp,
+        filter: FilterMode::Nearest,
+    };
+}
 
 pub struct Image {
     image: Rgb32FImage,
     width: u32,
     height: u32,
     scale: f32,
+    sampling: Sampling,
+    octaves: u32,
+    persistence: f32,
 }
 
 impl Image {
     pub fn new(image: Rgb32FImage, scale: f32) -> Self {
+        Self::with_sampling(image, scale, Sampling::TILING)
+    }
+
+    pub fn with_sampling(image: Rgb32FImage, scale: f32, sampling: Sampling) -> Self {
         let width = image.width();
         let height = image.height();
         Self {
@@ -19,21 +59,66 @@ impl Image {
             width,
             height,
             scale,
+            sampling,
+            octaves: 1,
+            persistence: 0.5,
         }
     }
 
+    // each octave doubles the frequency, so they all still repeat within one period
+    pub fn set_fbm(&mut self, octaves: u32, persistence: f32) {
+        self.octaves = octaves.max(1);
+        self.persistence = persistence;
+    }
+
+    // world distance after which sample repeats, with clamp it's where the edge is held instead
+    pub fn period(&self) -> f32 {
+        1.0 / self.scale
+    }
+
+    // sums the octaves and divides by the total amplitude, so the result stays in the same range
     pub fn sample(&self, u: f32, v: f32) -> f32 {
-        let u = wrap(u * self.scale, 0.0, 1.0) * self.width as f32;
-        let v = wrap(v * self.scale, 0.0, 1.0) * self.height as f32;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        for _ in 0..self.octaves {
+            sum += self.sample_octave(u * frequency, v * frequency) * amplitude;
+            total += amplitude;
+            amplitude *= self.persistence;
+            frequency *= 2.0;
+        }
+        sum / total
+    }
+
+    // normal of the surface `sample * amplitude`, stepping one texel to each side so the slope is
+    // as fine as the texture can resolve, flat ground points straight up
+    pub fn sample_normal(&self, u: f32, v: f32, amplitude: f32) -> Vec3 {
+        let step_u = 1.0 / (self.scale * self.width as f32);
+        let step_v = 1.0 / (self.scale * self.height as f32);
+        let du = (self.sample(u + step_u, v) - self.sample(u - step_u, v)) * amplitude;
+        let dv = (self.sample(u, v + step_v) - self.sample(u, v - step_v)) * amplitude;
+        Vec3::new(-du / (2.0 * step_u), 1.0, -dv / (2.0 * step_v)).normalize()
+    }
+
+    fn sample_octave(&self, u: f32, v: f32) -> f32 {
+        let u = self.address_uv(u * self.scale) * self.width as f32;
+        let v = self.address_uv(v * self.scale) * self.height as f32;
         let x = u as i32;
         let y = v as i32;
 
+        if self.sampling.filter == FilterMode::Nearest {
+            let x = self.address_texel(x, self.width);
+            let y = self.address_texel(y, self.height);
+            return self.image.get_pixel(x, y)[0];
+        }
+
         let mut vals = Vec::with_capacity(9);
 
         for i in -1..=1 {
-            let y = wrap(y + i, 0, self.height as i32 - 1) as u32;
+            let y = self.address_texel(y + i, self.height);
             for j in -1..=1 {
-                let x = wrap(x + j, 0, self.width as i32 - 1) as u32;
+                let x = self.address_texel(x + j, self.width);
                 let val = self.image.get_pixel(x, y)[0];
                 let dist = (Vec2::new(j as f32, i as f32) * 0.5).distance(Vec2::new(u, v).fract());
 
@@ -44,6 +129,22 @@ impl Image {
         let sum = vals.iter().fold(0.0, |acc, (e, _)| acc + e);
         vals.into_iter().map(|(e, val)| (e / sum) * val).sum()
     }
+
+    fn address_uv(&self, uv: f32) -> f32 {
+        match self.sampling.address {
+            AddressMode::Repeat => wrap(uv, 0.0, 1.0),
+            AddressMode::Clamp => uv.clamp(0.0, 1.0),
+        }
+    }
+
+    // uv 1.0 and the blur's neighbours can land one texel outside the image
+    fn address_texel(&self, texel: i32, size: u32) -> u32 {
+        let last = size as i32 - 1;
+        match self.sampling.address {
+            AddressMode::Repeat => wrap(texel, 0, last) as u32,
+            AddressMode::Clamp => texel.clamp(0, last) as u32,
+        }
+    }
 }
 
 fn wrap<T>(val: T, min: T, max: T) -> T
@@ -27,6 +27,21 @@ pub fn parse_colors(colors: &[(u32, Vec3)]) -> Vec<Vec3> {
     }
 }
 
+pub fn resample(colors: &[Vec3], width: u32) -> Vec<Vec3> {
+    match colors.len() {
+        0 => Vec::new(),
+        1 => vec![colors[0]; width as usize],
+        len => (0..width)
+            .map(|i| {
+                let x = i as f32 / (width - 1).max(1) as f32 * (len - 1) as f32;
+                let current = (x.floor() as usize).min(len - 2);
+                let t = x - current as f32;
+                colors[current] * (1.0 - t) + colors[current + 1] * t
+            })
+            .collect(),
+    }
+}
+
 pub fn parse_colors_linear(colors: &[(u32, Vec3)]) -> Vec<Vec3> {
     colors.iter().map(|(_, color)| *color).collect()
 }
@@ -1,19 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use is_none_or::IsNoneOr;
-use rand::{rngs::ThreadRng, Rng};
+use jandering_engine::types::Vec3;
+use rand::Rng;
 
-use super::RenderConfig;
+use super::{migrate, RenderConfig};
+
+// a sunflower head has a few thousand florets, anything past this is a typo
+const MAX_REPEAT: u32 = 10_000;
 
 #[derive(Debug, Clone)]
 pub enum Value {
     Range { min: f32, max: f32 },
+    SteppedRange { min: f32, max: f32, step: f32 },
     Exact(f32),
 }
 
 #[derive(Debug, Clone)]
 pub enum Values {
-    Multiple(Vec<Value>),
+    Multiple(Vec<(Value, f32)>),
     Exact(Value),
     Default,
 }
@@ -25,15 +30,20 @@ impl Values {
             let mut j = 1;
             for sym in tmp_chars {
                 if sym == ')' {
-                    let string = String::from_iter(
-                        chars
-                            .clone()
-                            .take(j)
-                            .filter(|&e| e.is_numeric() || matches!(e, '~' | ',' | '.' | '-')),
-                    );
+                    let string = String::from_iter(chars.clone().take(j).filter(|&e| {
+                        e.is_numeric() || matches!(e, '~' | ',' | '.' | '-' | ':' | '#')
+                    }));
                     let values = string
                         .split(',')
                         .flat_map(|string| {
+                            let (string, weight) = match string.split_once(':') {
+                                Some((string, weight)) => (string, weight.parse::<f32>().ok()?),
+                                None => (string, 1.0),
+                            };
+                            let (string, step) = match string.split_once('#') {
+                                Some((string, step)) => (string, step.parse::<f32>().ok()),
+                                None => (string, None),
+                            };
                             let nums = string
                                 .split('~')
                                 .flat_map(|e| e.parse::<f32>())
@@ -41,26 +51,29 @@ impl Values {
                             if nums.is_empty() {
                                 return None;
                             }
-                            if nums.len() == 1 {
-                                Some(Value::Exact(nums[0]))
+                            let value = if nums.len() == 1 {
+                                Value::Exact(nums[0])
                             } else {
-                                Some(Value::Range {
-                                    min: nums[0],
-                                    max: nums[nums.len() - 1],
-                                })
-                            }
+                                let min = nums[0];
+                                let max = nums[nums.len() - 1];
+                                match step {
+                                    Some(step) => Value::SteppedRange { min, max, step },
+                                    None => Value::Range { min, max },
+                                }
+                            };
+                            Some((value, weight))
                         })
                         .collect::<Vec<_>>();
 
                     chars.nth(j);
                     return if values.len() == 1 {
-                        Self::Exact(values[0].clone())
+                        Self::Exact(values[0].0.clone())
                     } else {
                         Self::Multiple(values)
                     };
                 }
 
-                if !sym.is_numeric() && !matches!(sym, '~' | ' ' | ',' | '.' | '-') {
+                if !sym.is_numeric() && !matches!(sym, '~' | ' ' | ',' | '.' | '-' | ':' | '#') {
                     break;
                 }
 
@@ -71,29 +84,89 @@ impl Values {
         Self::Default
     }
 
-    pub fn get(&self, default: f32, rng: &mut ThreadRng) -> f32 {
+    pub fn get(&self, default: f32, rng: &mut impl Rng) -> f32 {
         let val = match self {
             Values::Multiple(vec) => {
-                let i = rng.gen_range(0..vec.len());
-                &vec[i]
+                let total = vec.iter().fold(0.0, |acc, (_, weight)| acc + weight);
+                if total <= 0.0 {
+                    return default;
+                }
+                let n = rng.gen_range(0.0..total);
+                let mut t = 0.0;
+                let mut picked = &vec[vec.len() - 1].0;
+                for (value, weight) in vec {
+                    t += weight;
+                    if t > n {
+                        picked = value;
+                        break;
+                    }
+                }
+                picked
             }
             Values::Exact(val) => val,
             Values::Default => return default,
         };
 
-        match val {
-            Value::Range { min, max } => rng.gen_range(*min..*max),
-            Value::Exact(value) => *value,
+        match *val {
+            Value::Range { min, max } => sample(min, max, rng),
+            Value::SteppedRange { min, max, step } => {
+                if step <= 0.0 {
+                    return sample(min, max, rng);
+                }
+                let first = (min / step).ceil() as i32;
+                let last = (max / step).floor() as i32;
+                if first > last {
+                    return (min / step).round() * step;
+                }
+                rng.gen_range(first..=last) as f32 * step
+            }
+            Value::Exact(value) => value,
         }
     }
 }
 
+// gen_range panics on an empty range, but (5~5) is a fine way to write 5
+fn sample(min: f32, max: f32, rng: &mut impl Rng) -> f32 {
+    if min < max {
+        rng.gen_range(min..max)
+    } else {
+        min
+    }
+}
+
+fn parse_numbers(chars: &mut std::iter::Peekable<std::str::Chars>) -> Vec<f32> {
+    if chars.peek() != Some(&'(') {
+        return Vec::new();
+    }
+
+    let string = chars
+        .by_ref()
+        .skip(1)
+        .take_while(|&e| e != ')')
+        .collect::<String>();
+    string
+        .split(',')
+        .flat_map(|e| e.trim().parse::<f32>())
+        .collect()
+}
+
+fn parse_color(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<Vec3> {
+    if let [r, g, b] = parse_numbers(chars)[..] {
+        Some(Vec3::new(r, g, b))
+    } else {
+        None
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum LSymbol {
     Scope,
     ScopeEnd,
     Rule(char),
-    Object { id: char, age: u32 },
+    Object {
+        id: char,
+        age: u32,
+    },
     RotateX(Values),
     RotateNegX(Values),
     RotateY(Values),
@@ -101,36 +174,72 @@ pub enum LSymbol {
     RotateZ(Values),
     RotateNegZ(Values),
     Scale(Values),
+    SetColor(Option<Vec3>),
+    Upright,
+    // moves the turtle forward without drawing, the value is the length
+    Move(Values),
+    // multiplies or divides the width of every line drawn after it
+    Thinner(Values),
+    Thicker(Values),
+    // count copies of a rule in their own scopes, turning and scaling between them
+    Repeat {
+        count: u32,
+        rule: char,
+        angle: Option<f32>,
+        scale_step: f32,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LRule {
     pub result: Vec<LSymbol>,
     pub chance: f32,
     pub min_gen: Option<f32>,
     pub max_gen: Option<f32>,
+    // the rule only applies with these on either side of it, written `a < B > c = result`
+    pub left: Option<char>,
+    pub right: Option<char>,
+    // the B from that header, it has to be the rule the production is listed under
+    pub predecessor: Option<char>,
+}
+
+// the closest object or rule symbols on either side of a rule in the string it's in
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Neighbours {
+    pub left: Option<char>,
+    pub right: Option<char>,
+}
+
+impl LRule {
+    fn fits(&self, neighbours: Neighbours) -> bool {
+        self.left.is_none_or(|e| neighbours.left == Some(e))
+            && self.right.is_none_or(|e| neighbours.right == Some(e))
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LRuleSet {
     pub chance: f32,
     pub rules: Vec<LRule>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LRuleSets {
     current: usize,
     sets: Vec<LRuleSet>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct LSystemBuildConfig {
     pub iterations: u32,
+    // how many symbols a build may walk before it stops, rules that multiply every iteration
+    // would otherwise hang the page. 0 doesn't cap it
+    pub max_symbols: u32,
     pub initial: Vec<LSymbol>,
     pub rule_sets: HashMap<char, LRuleSets>,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct LConfig {
     pub rendering: RenderConfig,
     pub rules: LSystemBuildConfig,
@@ -143,7 +252,9 @@ mod json {
 
     use crate::l_system::RenderConfig;
 
-    use super::{LRule, LRuleSet, LRuleSets, LSymbol, LSystemBuildConfig, Values};
+    use super::{
+        parse_color, LRule, LRuleSet, LRuleSets, LSymbol, LSystemBuildConfig, Values, MAX_REPEAT,
+    };
 
     #[derive(Deserialize, Debug, Clone)]
     pub(crate) struct RuleJSON {
@@ -167,10 +278,16 @@ mod json {
     pub(crate) struct LSystemBuildConfigJSON {
         #[serde(default)]
         pub(crate) iterations: u32,
+        #[serde(default = "default_max_symbols")]
+        pub(crate) max_symbols: u32,
         pub(crate) initial: String,
         pub(crate) rules: HashMap<char, Vec<RuleSetJSON>>,
     }
 
+    fn default_max_symbols() -> u32 {
+        1_000_000
+    }
+
     #[derive(Deserialize)]
     pub(crate) struct LConfigJSON {
         pub(crate) rendering: RenderConfig,
@@ -181,6 +298,7 @@ mod json {
         fn from(val: LSystemBuildConfigJSON) -> Self {
             let LSystemBuildConfigJSON {
                 iterations,
+                max_symbols,
                 initial,
                 rules,
             } = val;
@@ -225,11 +343,17 @@ mod json {
                                              chance,
                                              min_gen,
                                              max_gen,
-                                         }| LRule {
-                                            result: string_to_symbols(result),
-                                            chance: chance.unwrap_or(divided_chance),
-                                            min_gen,
-                                            max_gen,
+                                         }| {
+                                            let (context, result) = split_context(&result);
+                                            LRule {
+                                                result: string_to_symbols(result.to_string()),
+                                                chance: chance.unwrap_or(divided_chance),
+                                                min_gen,
+                                                max_gen,
+                                                left: context.left,
+                                                right: context.right,
+                                                predecessor: context.predecessor,
+                                            }
                                         },
                                     )
                                     .collect()
@@ -250,20 +374,81 @@ mod json {
 
             LSystemBuildConfig {
                 iterations,
+                max_symbols,
                 initial,
                 rule_sets,
             }
         }
     }
 
+    #[derive(Default)]
+    struct Context {
+        left: Option<char>,
+        predecessor: Option<char>,
+        right: Option<char>,
+    }
+
+    // `a < B > c = result`, either side can be left out. `<` and `>` also turn the turtle, so they
+    // only mean context before the `=`, which isn't a symbol anywhere else. an `=` inside a
+    // trailing note doesn't count
+    fn split_context(result: &str) -> (Context, &str) {
+        let code = result.split('#').next().unwrap_or_default();
+        let Some((header, rest)) = code.find('=').map(|i| (&result[..i], &result[i + 1..])) else {
+            return (Context::default(), result);
+        };
+
+        let header = header
+            .chars()
+            .filter(|e| !e.is_whitespace())
+            .collect::<Vec<_>>();
+        let (left, header) = match header.iter().position(|e| *e == '<') {
+            Some(i) => (header[..i].last().copied(), &header[i + 1..]),
+            None => (None, &header[..]),
+        };
+        let (predecessor, right) = match header.iter().position(|e| *e == '>') {
+            Some(i) => (header[..i].first().copied(), header.get(i + 1).copied()),
+            None => (header.first().copied(), None),
+        };
+
+        let context = Context {
+            left,
+            predecessor,
+            right,
+        };
+        (context, rest)
+    }
+
     fn string_to_symbols(string: String) -> Vec<LSymbol> {
         let mut symbols = Vec::with_capacity(string.capacity());
         let mut chars = string.chars().peekable();
 
         while let Some(symbol) = chars.next() {
             match symbol {
+                // a note for whoever edits the json, runs to the end of the line. value lists
+                // use '#' for steps but they're read whole by Values::new and never get here
+                '#' => while chars.next_if(|e| *e != '\n').is_some() {},
                 '[' => symbols.push(LSymbol::Scope),
+                '\'' => symbols.push(LSymbol::SetColor(parse_color(&mut chars))),
                 ']' => symbols.push(LSymbol::ScopeEnd),
+                '$' => symbols.push(LSymbol::Upright),
+                '_' => symbols.push(LSymbol::Move(Values::new(&mut chars))),
+                '!' => symbols.push(LSymbol::Thinner(Values::new(&mut chars))),
+                '?' => symbols.push(LSymbol::Thicker(Values::new(&mut chars))),
+                // *(count[,angle[,scale_step]])R, the angle defaults to the golden angle
+                '*' => {
+                    let numbers = parse_numbers(&mut chars);
+                    let Some(rule) = chars.next_if(|e| e.is_ascii_uppercase()) else {
+                        continue;
+                    };
+                    symbols.push(LSymbol::Repeat {
+                        count: numbers
+                            .first()
+                            .map_or(1, |e| (e.max(0.0) as u32).min(MAX_REPEAT)),
+                        rule,
+                        angle: numbers.get(1).copied(),
+                        scale_step: numbers.get(2).copied().unwrap_or(1.0),
+                    });
+                }
                 '+' | '-' | '&' | '^' | '\\' | '/' | '>' | '<' | '|' => {
                     let values = Values::new(&mut chars);
                     let symbol = match symbol {
@@ -295,47 +480,196 @@ mod json {
 
 impl LConfig {
     pub fn from_json(json: String) -> Result<Self, String> {
-        match serde_json::from_str::<json::LConfigJSON>(&json) {
-            Ok(json::LConfigJSON { rendering, rules }) => Ok(Self {
+        let value = json5::from_str::<serde_json::Value>(&json).map_err(|err| err.to_string())?;
+        let value = migrate::migrate(value)?;
+        let config = match serde_json::from_value::<json::LConfigJSON>(value) {
+            Ok(json::LConfigJSON { rendering, rules }) => Self {
                 rendering,
                 rules: rules.into(),
-            }),
-            Err(err) => Err(err.to_string()),
+            },
+            Err(err) => return Err(err.to_string()),
+        };
+        config.validate().map_err(|errors| errors.join("\n"))?;
+        Ok(config)
+    }
+
+    // anything that would make the build silently draw less than the config says
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for (source, symbols) in self.symbol_lists() {
+            for symbol in symbols {
+                match symbol {
+                    LSymbol::Rule(id) | LSymbol::Repeat { rule: id, .. }
+                        if !self.rules.rule_sets.contains_key(id) =>
+                    {
+                        errors.push(format!("{source} references undefined rule '{id}'"));
+                    }
+                    LSymbol::Object { id, .. } if !self.rendering.shapes.contains_key(id) => {
+                        errors.push(format!("{source} references undefined shape '{id}'"));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for (id, rule_sets) in &self.rules.rule_sets {
+            if rule_sets.sets.is_empty() {
+                errors.push(format!("rule '{id}' has no rule sets"));
+            }
+            for (i, set) in rule_sets.sets.iter().enumerate() {
+                if set.rules.is_empty() {
+                    errors.push(format!("rule set {} of '{id}' has no rules", i + 1));
+                }
+                for rule in &set.rules {
+                    if let Some(predecessor) = rule.predecessor.filter(|e| e != id) {
+                        errors.push(format!(
+                            "rule under '{id}' has context for '{predecessor}' instead"
+                        ));
+                    }
+                }
+            }
+        }
+
+        errors.sort();
+        errors.dedup();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
     }
 
-    pub fn get_rule(&self, id: &char, rng: &mut ThreadRng, age: f32) -> Option<&[LSymbol]> {
+    // mistakes that don't change what gets built, the config still loads with them
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (id, shape) in &self.rendering.shapes {
+            if let Some(lut) = shape.lut() {
+                if !self.rendering.luts.iter().any(|e| e.name == lut) {
+                    warnings.push(format!("shape '{id}' references undefined lut '{lut}'"));
+                }
+            }
+        }
+
+        if let Some(lut) = self.rendering.fruit.as_ref().and_then(|e| e.lut.as_ref()) {
+            if !self.rendering.luts.iter().any(|e| &e.name == lut) {
+                warnings.push(format!("fruit references undefined lut '{lut}'"));
+            }
+        }
+
+        let reachable = self.reachable_rules();
+        for key in self.rules.rule_sets.keys() {
+            if !reachable.contains(key) {
+                warnings.push(format!("rule '{key}' is never used"));
+            }
+        }
+
+        warnings.sort();
+        warnings.dedup();
+        warnings
+    }
+
+    // why a build came out without a single shape, so a broken grammar can be told from an empty one
+    pub fn explain_empty(&self) -> String {
+        let draws = |symbols: &[LSymbol]| {
+            symbols.iter().any(|symbol| {
+                matches!(symbol, LSymbol::Object { id, .. } if self.rendering.shapes.contains_key(id))
+            })
+        };
+        let draws_initially = draws(&self.rules.initial);
+        let draws_in_rules = self
+            .reachable_rules()
+            .into_iter()
+            .any(|id| self.rule_results(id).any(draws));
+
+        if !draws_initially && !draws_in_rules {
+            "no reachable symbol draws a defined shape, check the object symbols against rendering.shapes"
+                .to_string()
+        } else {
+            "every shape was pruned, or the rules picked for this seed draw nothing".to_string()
+        }
+    }
+
+    fn reachable_rules(&self) -> HashSet<char> {
+        let mut reachable = HashSet::new();
+        let mut stack = referenced_rules(&self.rules.initial).collect::<Vec<_>>();
+        while let Some(id) = stack.pop() {
+            if reachable.insert(id) {
+                stack.extend(self.rule_results(id).flat_map(referenced_rules));
+            }
+        }
+        reachable
+    }
+
+    fn rule_results(&self, id: char) -> impl Iterator<Item = &[LSymbol]> {
+        self.rules
+            .rule_sets
+            .get(&id)
+            .into_iter()
+            .flat_map(|sets| sets.sets.iter())
+            .flat_map(|set| set.rules.iter().map(|rule| rule.result.as_slice()))
+    }
+
+    fn symbol_lists(&self) -> impl Iterator<Item = (String, &[LSymbol])> {
+        let rules = self.rules.rule_sets.keys().flat_map(move |key| {
+            self.rule_results(*key)
+                .map(move |result| (format!("rule '{key}'"), result))
+        });
+
+        std::iter::once(("initial".to_string(), self.rules.initial.as_slice())).chain(rules)
+    }
+
+    pub fn get_rule(
+        &self,
+        id: &char,
+        rng: &mut impl Rng,
+        age: f32,
+        neighbours: Neighbours,
+    ) -> Option<&[LSymbol]> {
         self.rules.rule_sets.get(id).and_then(|sets| {
             let rules = &sets.sets[sets.current].rules;
-            pick_rule(rules, rng, age)
+            pick_rule(rules, rng, age, neighbours)
         })
     }
 
-    pub fn randomize_rule_sets(&mut self, n: Option<u32>, rng: &mut ThreadRng) {
-        if let Some(n) = n {
-            let mut indices = self.rules.rule_sets.keys().copied().collect::<Vec<_>>();
-            for _ in 0..n.min(indices.len() as u32) {
-                let i = rng.gen_range(0..indices.len());
-                let key = indices.remove(i);
-                let set = self.rules.rule_sets.get_mut(&key).unwrap();
-                set.current = rng.gen_range(0..set.sets.len());
-            }
+    // keys are sorted so the picks only depend on the rng, a hash map's order changes with
+    // every parse. new_plant calls this with the plant's seeded rng before building, so whatever
+    // was picked before never leaks into a plant
+    pub fn randomize_rule_sets(&mut self, n: Option<u32>, rng: &mut impl Rng) {
+        let mut keys = self.rules.rule_sets.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable();
+
+        let keys = if let Some(n) = n {
+            (0..n.min(keys.len() as u32))
+                .map(|_| keys.remove(rng.gen_range(0..keys.len())))
+                .collect()
         } else {
-            self.rules
-                .rule_sets
-                .iter_mut()
-                .for_each(|(_, set)| set.current = rng.gen_range(0..set.sets.len()));
+            keys
+        };
+
+        for key in keys {
+            let set = self.rules.rule_sets.get_mut(&key).unwrap();
+            set.current = rng.gen_range(0..set.sets.len());
         }
     }
 }
 
+fn referenced_rules(symbols: &[LSymbol]) -> impl Iterator<Item = char> + '_ {
+    symbols.iter().filter_map(|symbol| match symbol {
+        LSymbol::Rule(id) | LSymbol::Repeat { rule: id, .. } => Some(*id),
+        _ => None,
+    })
+}
+
 fn pick_rule<'rules>(
     rules: &'rules [LRule],
-    rng: &mut ThreadRng,
+    rng: &mut impl Rng,
     age: f32,
+    neighbours: Neighbours,
 ) -> Option<&'rules [LSymbol]> {
     let filtered = rules.iter().filter(|rule| {
-        rule.min_gen.is_none_or(|v| age >= v) && rule.max_gen.is_none_or(|v| age < v)
+        rule.min_gen.is_none_or(|v| age >= v)
+            && rule.max_gen.is_none_or(|v| age < v)
+            && rule.fits(neighbours)
     });
     let max_chance = filtered.clone().fold(0.0, |acc, rule| acc + rule.chance);
     if max_chance <= 0.0 {
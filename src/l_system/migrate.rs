@@ -0,0 +1,89 @@
+use serde_json::{json, Value};
+
+const LEGACY_LUT: &str = "legacy colors";
+
+// each entry upgrades a config from version i + 1 to i + 2, append one whenever the schema changes
+const MIGRATIONS: &[fn(&mut Value)] = &[];
+
+pub const CURRENT_VERSION: u64 = MIGRATIONS.len() as u64 + 1;
+
+// configs without a version field are treated as version 1, the first versioned schema.
+// only touches the parsed value, so it works the same wherever the text came from
+pub fn migrate(mut config: Value) -> Result<Value, String> {
+    let version = match config.get("version") {
+        None => {
+            upgrade_legacy(&mut config);
+            1
+        }
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("config version {version} is not a whole number"))?,
+    };
+
+    if version == 0 || version > CURRENT_VERSION {
+        return Err(format!(
+            "config version {version} is not supported, this build reads versions 1 to {CURRENT_VERSION}"
+        ));
+    }
+
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(&mut config);
+    }
+
+    if let Some(object) = config.as_object_mut() {
+        object.insert("version".to_string(), CURRENT_VERSION.into());
+    }
+
+    Ok(config)
+}
+
+// the old lsystem.json layout predates versioning, so it's recognised by shape instead:
+// rules listed directly under their key instead of in rule sets, plus a `colors` gradient
+// and a global `width_mod` in rendering. anything already in the current layout is left alone
+fn upgrade_legacy(config: &mut Value) {
+    if let Some(rules) = config
+        .pointer_mut("/rules/rules")
+        .and_then(Value::as_object_mut)
+    {
+        for rules in rules.values_mut() {
+            let is_legacy = rules
+                .as_array()
+                .and_then(|e| e.first())
+                .is_some_and(|e| e.get("result").is_some());
+            if is_legacy {
+                *rules = json!([{ "rules": rules.take() }]);
+            }
+        }
+    }
+
+    let Some(rendering) = config.get_mut("rendering").and_then(Value::as_object_mut) else {
+        return;
+    };
+
+    let width_mod = rendering.remove("width_mod").and_then(|e| e.as_f64());
+    let colors = rendering.remove("colors");
+
+    if let Some(colors) = &colors {
+        let luts = rendering.entry("luts").or_insert_with(|| json!([]));
+        if let Some(luts) = luts.as_array_mut() {
+            luts.push(json!({ "name": LEGACY_LUT, "colors": colors }));
+        }
+    }
+
+    let Some(shapes) = rendering.get_mut("shapes").and_then(Value::as_object_mut) else {
+        return;
+    };
+    // shapes are externally tagged, { "Line": { .. } }
+    for shape in shapes.values_mut().filter_map(Value::as_object_mut) {
+        for fields in shape.values_mut().filter_map(Value::as_object_mut) {
+            if let (Some(width_mod), Some(width)) =
+                (width_mod, fields.get("width").and_then(Value::as_f64))
+            {
+                fields.insert("width".to_string(), json!(width * width_mod));
+            }
+            if colors.is_some() && !fields.contains_key("lut") {
+                fields.insert("lut".to_string(), json!(LEGACY_LUT));
+            }
+        }
+    }
+}
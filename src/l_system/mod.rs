@@ -1,25 +1,273 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use jandering_engine::types::{Qua, Vec3};
-use rand::rngs::ThreadRng;
+use rand::Rng;
 use serde::Deserialize;
 
-use self::config::{LConfig, LSymbol};
+use self::config::{LConfig, LSymbol, Neighbours};
 
 pub mod colors;
 pub mod config;
+mod migrate;
+
+const UPRIGHT_EPSILON: f32 = 1e-3;
+// 360 * (2 - phi) degrees
+const GOLDEN_ANGLE: f32 = 2.399_963;
 
 #[derive(serde::Deserialize, Clone)]
 enum Shape {
-    Branch { width: f32, length: f32 },
-    Line { width: f32, length: f32 },
-    Circle { size: f32 },
+    Branch {
+        width: f32,
+        length: f32,
+        #[serde(default)]
+        lut: Option<String>,
+        // total bend over the length around the turtle's x, 0 keeps the line straight
+        #[serde(default)]
+        curvature: f32,
+        #[serde(default)]
+        subdivisions: u32,
+        #[serde(flatten)]
+        material: Material,
+    },
+    Line {
+        width: f32,
+        length: f32,
+        #[serde(default)]
+        lut: Option<String>,
+        // total bend over the length around the turtle's x, 0 keeps the line straight
+        #[serde(default)]
+        curvature: f32,
+        #[serde(default)]
+        subdivisions: u32,
+        #[serde(flatten)]
+        material: Material,
+    },
+    Circle {
+        size: f32,
+        #[serde(default)]
+        lut: Option<String>,
+        #[serde(flatten)]
+        material: Material,
+    },
+}
+
+// written straight into the shape, the default is fully matte so shapes without it look as before
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct Material {
+    pub metallic: f32,
+    pub roughness: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            metallic: 0.0,
+            roughness: 1.0,
+        }
+    }
+}
+
+impl Shape {
+    fn lut(&self) -> Option<&str> {
+        match self {
+            Shape::Branch { lut, .. } | Shape::Line { lut, .. } | Shape::Circle { lut, .. } => {
+                lut.as_deref()
+            }
+        }
+    }
+
+    fn material(&self) -> Material {
+        match self {
+            Shape::Branch { material, .. }
+            | Shape::Line { material, .. }
+            | Shape::Circle { material, .. } => *material,
+        }
+    }
 }
 
-#[derive(Deserialize, Clone, Default)]
+// decorations put on the end of every mature line nothing else grows from
+#[derive(Deserialize, Clone)]
+struct FruitConfig {
+    min_age: f32,
+    size: (f32, f32),
+    #[serde(default)]
+    color: Option<[f32; 3]>,
+    #[serde(default)]
+    lut: Option<String>,
+    #[serde(flatten)]
+    material: Material,
+}
+
+#[derive(Deserialize, Clone)]
+struct NamedLut {
+    name: String,
+    colors: Vec<(u32, [f32; 3])>,
+}
+
+impl NamedLut {
+    fn parse(&self) -> Vec<Vec3> {
+        let colors = self
+            .colors
+            .iter()
+            .map(|(age, color)| (*age, Vec3::from(*color)))
+            .collect::<Vec<_>>();
+        colors::parse_colors(&colors)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+enum AngleUnit {
+    #[default]
+    Degrees,
+    Radians,
+}
+
+#[derive(Deserialize, Clone)]
 pub struct RenderConfig {
     default_angle_change: f32,
+    // per symbol pair overrides of the above, named after the classic turtle rather than the
+    // axes they turn around: `+ -` yaw, `& ^` pitch and `\ /` roll
+    #[serde(default)]
+    default_yaw: Option<f32>,
+    #[serde(default)]
+    default_pitch: Option<f32>,
+    #[serde(default)]
+    default_roll: Option<f32>,
+    #[serde(default)]
+    angle_unit: AngleUnit,
+    // multiplies every rotation, flattens (< 1) or exaggerates (> 1) the whole plant
+    #[serde(default = "default_angle_scale")]
+    pub angle_scale: f32,
+    // lines thinner than this (width times the turtle scale) are dropped along with
+    // everything grown off them, 0 disables
+    #[serde(default)]
+    prune_width: f32,
+    // same for lines from a later generation than this (age is 0..1 over the iterations), 0 disables
+    #[serde(default)]
+    prune_age: f32,
+    // how far `_` moves the turtle when it doesn't say, scaled like line lengths
+    #[serde(default = "default_move_length")]
+    move_length: f32,
+    // what `!` multiplies the width by and `?` divides it by when they don't say
+    #[serde(default = "default_width_step")]
+    width_step: f32,
+    // how many times circles split every triangle into four, 0 is the bare icosahedron
+    #[serde(default)]
+    sphere_subdivisions: u32,
+    // widens lines by how much grows above them, off keeps every line at its configured width
+    #[serde(default)]
+    taper: bool,
+    // closes the end of every line nothing else grows from, so you can't see into the tube
+    #[serde(default)]
+    cap_branches: bool,
+    // narrows every line towards the lines growing out of it, off keeps each one a plain tube
+    #[serde(default)]
+    join_widths: bool,
+    // gives every triangle of a line its own normal instead of blending them around the tube
+    #[serde(default)]
+    flat_shading: bool,
+    // world space direction the heading is pulled towards after every line, [0, -1, 0] droops.
+    // negative elasticity pushes away from it instead, 0 leaves lines alone
+    #[serde(default)]
+    tropism_direction: Option<[f32; 3]>,
+    #[serde(default)]
+    elasticity: f32,
     shapes: HashMap<char, Shape>,
+    #[serde(default)]
+    fruit: Option<FruitConfig>,
+    #[serde(default)]
+    luts: Vec<NamedLut>,
+}
+
+fn default_angle_scale() -> f32 {
+    1.0
+}
+
+fn default_move_length() -> f32 {
+    1.0
+}
+
+fn default_width_step() -> f32 {
+    0.7
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            default_angle_change: 0.0,
+            default_yaw: None,
+            default_pitch: None,
+            default_roll: None,
+            angle_unit: AngleUnit::default(),
+            angle_scale: default_angle_scale(),
+            prune_width: 0.0,
+            prune_age: 0.0,
+            move_length: default_move_length(),
+            width_step: default_width_step(),
+            sphere_subdivisions: 0,
+            taper: false,
+            cap_branches: false,
+            join_widths: false,
+            flat_shading: false,
+            tropism_direction: None,
+            elasticity: 0.0,
+            shapes: HashMap::new(),
+            fruit: None,
+            luts: Vec::new(),
+        }
+    }
+}
+
+impl RenderConfig {
+    // rule values and default_angle_change are both in angle_unit, the result is in radians
+    fn angle(&self, value: f32) -> f32 {
+        let radians = match self.angle_unit {
+            AngleUnit::Degrees => value.to_radians(),
+            AngleUnit::Radians => value,
+        };
+        radians * self.angle_scale
+    }
+
+    fn default_angle(&self, symbol: &LSymbol) -> f32 {
+        let per_axis = match symbol {
+            LSymbol::RotateY(_) | LSymbol::RotateNegY(_) => self.default_yaw,
+            LSymbol::RotateX(_) | LSymbol::RotateNegX(_) => self.default_pitch,
+            LSymbol::RotateZ(_) | LSymbol::RotateNegZ(_) => self.default_roll,
+            _ => None,
+        };
+        per_axis.unwrap_or(self.default_angle_change)
+    }
+
+    fn prunes(&self, shape: &RenderShape, scale: f32) -> bool {
+        match *shape {
+            RenderShape::Line {
+                start_width, age, ..
+            } => {
+                (self.prune_width > 0.0 && start_width * scale < self.prune_width)
+                    || (self.prune_age > 0.0 && age > self.prune_age)
+            }
+            RenderShape::Circle { .. } => false,
+        }
+    }
+
+    // row 0 of the lut texture is the gradient from the page, named luts follow in order
+    pub fn luts(&self) -> Vec<Vec<Vec3>> {
+        self.luts.iter().map(NamedLut::parse).collect()
+    }
+
+    pub fn named_lut(&self, name: &str) -> Option<Vec<Vec3>> {
+        self.luts
+            .iter()
+            .find(|e| e.name == name)
+            .map(NamedLut::parse)
+    }
+
+    fn lut_index(&self, name: Option<&str>) -> u32 {
+        name.and_then(|name| self.luts.iter().position(|e| e.name == name))
+            .map_or(0, |i| i as u32 + 1)
+    }
 }
 
 #[derive(Debug)]
@@ -27,14 +275,33 @@ pub enum RenderShape {
     Line {
         start: Vec3,
         end: Vec3,
-        width: f32,
+        // points between start and end when the line is curved
+        curve: Vec<Vec3>,
+        // the tube goes from one to the other, they're the same unless widths get joined
+        start_width: f32,
+        end_width: f32,
         age: f32,
         last_age: f32,
+        color: Option<Vec3>,
+        lut: u32,
+        material: Material,
+        // index of the line this one grows from, none for lines starting at the root
+        parent: Option<usize>,
+        // the end gets closed off, set for tips when caps are on
+        cap: bool,
+        flat: bool,
     },
     Circle {
         size: f32,
         pos: Vec3,
         age: f32,
+        // age of the branch it sits on, the side facing back along `heading` takes it
+        last_age: f32,
+        heading: Vec3,
+        subdivisions: u32,
+        color: Option<Vec3>,
+        lut: u32,
+        material: Material,
     },
 }
 
@@ -43,7 +310,14 @@ struct State {
     rotation: Qua,
     position: Vec3,
     scale: f32,
+    // multiplies the shape width, unlike scale it leaves lengths alone
+    width: f32,
     age: f32,
+    color: Option<Vec3>,
+    // set once a line got pruned, scopes opened after inherit it so no floating children are left
+    pruned: bool,
+    // the last line this turtle drew, scopes opened after grow from it too
+    parent: Option<usize>,
 }
 
 impl State {
@@ -52,6 +326,10 @@ impl State {
             rotation,
             position,
             scale,
+            width,
+            color,
+            pruned,
+            parent,
             ..
         } = *self;
 
@@ -59,18 +337,36 @@ impl State {
             rotation,
             position,
             scale,
+            width,
             age,
+            color,
+            pruned,
+            parent,
         }
     }
 }
 
-pub fn build(config: &LConfig, rng: &mut ThreadRng) -> Vec<RenderShape> {
+pub fn build(config: &LConfig, rng: &mut impl Rng) -> Vec<RenderShape> {
+    build_checked(config, rng).0
+}
+
+// same as build, also says whether the symbol budget ran out before the whole plant was walked
+pub fn build_checked(config: &LConfig, rng: &mut impl Rng) -> (Vec<RenderShape>, bool) {
     let mut states = vec![State {
         scale: 1.0,
+        width: 1.0,
         ..Default::default()
     }];
 
     let mut shapes = Vec::new();
+    let limit = match config.rules.max_symbols {
+        0 => u32::MAX,
+        max => max,
+    };
+    let mut budget = Budget {
+        left: limit,
+        cut: false,
+    };
 
     build_symbols(
         &mut states,
@@ -78,10 +374,206 @@ pub fn build(config: &LConfig, rng: &mut ThreadRng) -> Vec<RenderShape> {
         &config.rules.initial.clone(),
         config,
         rng,
-        0,
+        Origin::default(),
+        &mut budget,
     );
 
+    if budget.cut {
+        log::warn!(
+            "stopped building after {} symbols, keeping the {} shapes built so far",
+            limit,
+            shapes.len()
+        );
+    }
+
+    if config.rendering.taper {
+        taper(&mut shapes);
+    }
+
+    if config.rendering.join_widths {
+        join_widths(&mut shapes);
+    }
+
+    if config.rendering.cap_branches {
+        cap_tips(&mut shapes);
+    }
+
+    if let Some(fruit) = &config.rendering.fruit {
+        add_fruit(&mut shapes, fruit, &config.rendering, rng);
+    }
+
+    (shapes, budget.cut)
+}
+
+// symbols a build may still walk. using the last one up is fine, cut is only set once a symbol
+// had to be dropped for it
+struct Budget {
+    left: u32,
+    cut: bool,
+}
+
+impl Budget {
+    fn take(&mut self) -> bool {
+        if self.left == 0 {
+            self.cut = true;
+            return false;
+        }
+        self.left -= 1;
+        true
+    }
+}
+
+// da vinci's rule, a branch's area matches the areas of everything it carries, so the width
+// grows with the square root of the line length above it. shapes are pushed parents first, so
+// walking them backwards has every subtree summed up before its parent is reached
+fn taper(shapes: &mut [RenderShape]) {
+    let lengths = line_lengths(shapes);
+
+    let mut carried = lengths.clone();
+    for i in (0..shapes.len()).rev() {
+        if let RenderShape::Line {
+            parent: Some(parent),
+            ..
+        } = shapes[i]
+        {
+            carried[parent] += carried[i];
+        }
+    }
+
+    for ((shape, length), carried) in shapes.iter_mut().zip(lengths).zip(carried) {
+        if let RenderShape::Line {
+            start_width,
+            end_width,
+            ..
+        } = shape
+        {
+            if length > 0.0 {
+                let scale = (carried / length).sqrt();
+                *start_width *= scale;
+                *end_width *= scale;
+            }
+        }
+    }
+}
+
+// the mesh makes the radius the width times the length, so the end of a line is matched to the
+// radius its widest child starts with rather than to its width. tips keep their start width
+fn join_widths(shapes: &mut [RenderShape]) {
+    let lengths = line_lengths(shapes);
+
+    let mut radii = vec![None::<f32>; shapes.len()];
+    for (shape, length) in shapes.iter().zip(&lengths) {
+        if let RenderShape::Line {
+            parent: Some(parent),
+            start_width,
+            ..
+        } = *shape
+        {
+            let radius = start_width * length;
+            radii[parent] = Some(radii[parent].map_or(radius, |e| e.max(radius)));
+        }
+    }
+
+    for ((shape, length), radius) in shapes.iter_mut().zip(lengths).zip(radii) {
+        if let (RenderShape::Line { end_width, .. }, Some(radius)) = (shape, radius) {
+            if length > 0.0 {
+                *end_width = radius / length;
+            }
+        }
+    }
+}
+
+fn line_lengths(shapes: &[RenderShape]) -> Vec<f32> {
+    shapes
+        .iter()
+        .map(|shape| match shape {
+            RenderShape::Line {
+                start, end, curve, ..
+            } => std::iter::once(start)
+                .chain(curve)
+                .chain(std::iter::once(end))
+                .collect::<Vec<_>>()
+                .windows(2)
+                .map(|e| e[0].distance(*e[1]))
+                .sum(),
+            RenderShape::Circle { .. } => 0.0,
+        })
+        .collect()
+}
+
+// a line is a tip when no other line starts where it ends, positions are copied
+// straight from the turtle so comparing bits is exact
+fn position_key(pos: Vec3) -> [u32; 3] {
+    pos.to_array().map(f32::to_bits)
+}
+
+fn line_starts(shapes: &[RenderShape]) -> HashSet<[u32; 3]> {
     shapes
+        .iter()
+        .filter_map(|shape| match shape {
+            RenderShape::Line { start, .. } => Some(position_key(*start)),
+            RenderShape::Circle { .. } => None,
+        })
+        .collect()
+}
+
+fn cap_tips(shapes: &mut [RenderShape]) {
+    let starts = line_starts(shapes);
+    for shape in shapes {
+        if let RenderShape::Line { end, cap, .. } = shape {
+            *cap = !starts.contains(&position_key(*end));
+        }
+    }
+}
+
+fn add_fruit(
+    shapes: &mut Vec<RenderShape>,
+    fruit: &FruitConfig,
+    render_config: &RenderConfig,
+    rng: &mut impl Rng,
+) {
+    let starts = line_starts(shapes);
+
+    let lut = render_config.lut_index(fruit.lut.as_deref());
+    let (min_size, max_size) = fruit.size;
+    let tips = shapes
+        .iter()
+        .filter_map(|shape| match *shape {
+            RenderShape::Line {
+                start, end, age, ..
+            } if age >= fruit.min_age && !starts.contains(&position_key(end)) => {
+                Some((end, age, (end - start).normalize_or_zero()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // same age as the tip so they show up once the growth animation reaches it
+    shapes.extend(
+        tips.into_iter()
+            .map(|(pos, age, heading)| RenderShape::Circle {
+                size: if max_size > min_size {
+                    rng.gen_range(min_size..max_size)
+                } else {
+                    min_size
+                },
+                pos,
+                age,
+                last_age: age,
+                heading,
+                subdivisions: render_config.sphere_subdivisions,
+                color: fruit.color.map(Vec3::from),
+                lut,
+                material: fruit.material,
+            }),
+    );
+}
+
+// where a production came from, `outer` are the objects around the rule it replaced
+#[derive(Clone, Copy, Default)]
+struct Origin {
+    iteration: u32,
+    outer: Neighbours,
 }
 
 fn build_symbols(
@@ -89,10 +581,11 @@ fn build_symbols(
     shapes: &mut Vec<RenderShape>,
     symbols: &[LSymbol],
     config: &LConfig,
-    rng: &mut ThreadRng,
-    iteration: u32,
+    rng: &mut impl Rng,
+    origin: Origin,
+    budget: &mut Budget,
 ) {
-    let age = iteration as f32 / config.rules.iterations as f32;
+    let age = origin.iteration as f32 / config.rules.iterations as f32;
 
     let symbol_to_axis = |symbol: &LSymbol| match &symbol {
         LSymbol::RotateY(_) => Vec3::Y,
@@ -104,7 +597,12 @@ fn build_symbols(
         _ => Vec3::ZERO,
     };
 
-    for symbol in symbols {
+    for (i, symbol) in symbols.iter().enumerate() {
+        // once it runs out every level of the recursion returns with what it has
+        if !budget.take() {
+            return;
+        }
+
         match symbol {
             LSymbol::Scope => states.push(states.last().unwrap().clone(age)),
             LSymbol::ScopeEnd => {
@@ -115,10 +613,15 @@ fn build_symbols(
                 }
             }
             LSymbol::Object { id, .. } => {
-                if let Some(shape) =
-                    get_shape(id, age, &config.rendering, states.last_mut().unwrap())
-                {
-                    shapes.push(shape)
+                let state = states.last_mut().unwrap();
+                if let Some(shape) = get_shape(id, age, &config.rendering, state) {
+                    state.pruned |= config.rendering.prunes(&shape, state.scale);
+                    if !state.pruned {
+                        if let RenderShape::Line { .. } = shape {
+                            state.parent = Some(shapes.len());
+                        }
+                        shapes.push(shape)
+                    }
                 }
             }
             LSymbol::RotateX(values)
@@ -127,24 +630,214 @@ fn build_symbols(
             | LSymbol::RotateNegY(values)
             | LSymbol::RotateZ(values)
             | LSymbol::RotateNegZ(values) => {
-                let angle = values.get(config.rendering.default_angle_change, rng);
-                states.last_mut().unwrap().rotation *=
-                    Qua::from_axis_angle(symbol_to_axis(symbol), angle.to_radians());
+                let angle = values.get(config.rendering.default_angle(symbol), rng);
+                let rotation =
+                    Qua::from_axis_angle(symbol_to_axis(symbol), config.rendering.angle(angle));
+                let state = states.last_mut().unwrap();
+                // long chains of products drift off unit length and start skewing segments
+                state.rotation = (state.rotation * rotation).normalize();
             }
             LSymbol::Scale(values) => {
                 states.last_mut().unwrap().scale *= values.get(1.0, rng);
             }
+            LSymbol::SetColor(color) => {
+                states.last_mut().unwrap().color = *color;
+            }
+            LSymbol::Upright => {
+                let state = states.last_mut().unwrap();
+                state.rotation = upright(state.rotation);
+            }
+            LSymbol::Thinner(values) => {
+                states.last_mut().unwrap().width *= values.get(config.rendering.width_step, rng);
+            }
+            LSymbol::Thicker(values) => {
+                let step = values.get(config.rendering.width_step, rng);
+                if step != 0.0 {
+                    states.last_mut().unwrap().width /= step;
+                }
+            }
+            LSymbol::Move(values) => {
+                let length = values.get(config.rendering.move_length, rng);
+                advance(states.last_mut().unwrap(), length, 0.0, 1);
+            }
             LSymbol::Rule(id) => {
                 if age > 1.0 {
                     continue;
                 }
 
-                if let Some(rule) = config.get_rule(id, rng, age) {
-                    build_symbols(states, shapes, rule, config, rng, iteration + 1);
+                let (context, outer) = neighbours(symbols, i, origin.outer);
+                if let Some(rule) = config.get_rule(id, rng, age, context) {
+                    let origin = Origin {
+                        iteration: origin.iteration + 1,
+                        outer,
+                    };
+                    build_symbols(states, shapes, rule, config, rng, origin, budget);
+                }
+            }
+            LSymbol::Repeat {
+                count,
+                rule,
+                angle,
+                scale_step,
+            } => {
+                if age > 1.0 {
+                    continue;
+                }
+
+                let angle = angle.map_or(GOLDEN_ANGLE * config.rendering.angle_scale, |e| {
+                    config.rendering.angle(e)
+                });
+                let turn = Qua::from_axis_angle(Vec3::Y, angle);
+                let (context, outer) = neighbours(symbols, i, origin.outer);
+
+                // the turtle is left as it was, truncating also guards against unbalanced rules
+                let depth = states.len();
+                states.push(states.last().unwrap().clone(age));
+                for _ in 0..*count {
+                    // every copy is charged even when its rule doesn't apply, otherwise a huge
+                    // count would spin here cloning states without ever touching the budget
+                    if !budget.take() {
+                        break;
+                    }
+
+                    states.push(states.last().unwrap().clone(age));
+                    if let Some(rule) = config.get_rule(rule, rng, age, context) {
+                        let origin = Origin {
+                            iteration: origin.iteration + 1,
+                            outer,
+                        };
+                        build_symbols(states, shapes, rule, config, rng, origin, budget);
+                    }
+                    states.truncate(depth + 1);
+
+                    let state = states.last_mut().unwrap();
+                    state.rotation = (state.rotation * turn).normalize();
+                    state.scale *= scale_step;
                 }
+                states.truncate(depth);
+            }
+        }
+    }
+}
+
+// rules expand in place instead of rewriting a whole string, so the context is what's around the
+// rule in the production (or initial string) it came from. turtle commands don't count, the left
+// side steps out of the branch it's in and the right side skips whole branches but stops at the
+// end of its own. running off either end of the production falls back to `outer`, the objects
+// around the rule that produced it. rules there get rewritten in the same step as that one, so in
+// a rewritten string they'd have turned into their own productions and aren't neighbours.
+// returns the rule's context and the objects its production sees past either of its ends
+fn neighbours(symbols: &[LSymbol], index: usize, outer: Neighbours) -> (Neighbours, Neighbours) {
+    let is_neighbour =
+        |symbol: &LSymbol| matches!(symbol, LSymbol::Rule(_) | LSymbol::Object { .. });
+
+    let mut depth = 0u32;
+    let left = symbols[..index].iter().rev().find(|symbol| {
+        match symbol {
+            LSymbol::ScopeEnd => depth += 1,
+            LSymbol::Scope => depth = depth.saturating_sub(1),
+            _ if depth == 0 => return is_neighbour(*symbol),
+            _ => {}
+        }
+        false
+    });
+
+    let mut depth = 0u32;
+    let mut right = None;
+    let mut right_outer = outer.right;
+    for symbol in &symbols[index + 1..] {
+        match symbol {
+            LSymbol::Scope => depth += 1,
+            LSymbol::ScopeEnd if depth == 0 => {
+                right_outer = None;
+                break;
             }
+            LSymbol::ScopeEnd => depth -= 1,
+            _ if depth == 0 && is_neighbour(symbol) => {
+                right = Some(symbol);
+                break;
+            }
+            _ => {}
         }
     }
+
+    let id = |symbol: &LSymbol| match symbol {
+        LSymbol::Rule(id) | LSymbol::Object { id, .. } => Some(*id),
+        _ => None,
+    };
+    let object = |symbol: &LSymbol| match symbol {
+        LSymbol::Object { id, .. } => Some(*id),
+        _ => None,
+    };
+
+    let context = Neighbours {
+        left: left.map_or(outer.left, id),
+        right: right.map_or(right_outer, id),
+    };
+    let objects = Neighbours {
+        left: left.map_or(outer.left, object),
+        right: right.map_or(right_outer, object),
+    };
+    (context, objects)
+}
+
+// rolls around the heading until the turtle's left (local x) is horizontal,
+// a heading that points (almost) straight up or down has no such roll so it's left alone
+fn upright(rotation: Qua) -> Qua {
+    let heading = rotation.mul_vec3(Vec3::Y);
+    let left = rotation.mul_vec3(Vec3::X);
+
+    let horizontal = Vec3::Y.cross(heading);
+    if horizontal.length() < UPRIGHT_EPSILON {
+        return rotation;
+    }
+    let horizontal = horizontal.normalize();
+    // of the two horizontal directions take the one needing the smaller roll
+    let horizontal = if horizontal.dot(left) < 0.0 {
+        -horizontal
+    } else {
+        horizontal
+    };
+
+    let angle = heading
+        .dot(left.cross(horizontal))
+        .atan2(left.dot(horizontal));
+    (Qua::from_axis_angle(heading, angle) * rotation).normalize()
+}
+
+// turns the heading towards heading + elasticity * direction. that sum always lies between the
+// heading and the direction (or its opposite for negative elasticity), so it can't overshoot,
+// only a heading exactly opposite the pull has nowhere to go and is left alone
+fn tropism(state: &mut State, direction: Vec3, elasticity: f32) {
+    if elasticity == 0.0 {
+        return;
+    }
+
+    let heading = state.rotation.mul_vec3(Vec3::Y);
+    let target = (heading + direction * elasticity).normalize_or_zero();
+    if target == Vec3::ZERO {
+        return;
+    }
+    // the arc is in world space, so it goes in front of the turtle's rotation
+    state.rotation = (Qua::from_rotation_arc(heading, target) * state.rotation).normalize();
+}
+
+// walks the turtle along a line and returns the points it passes, ending with the end point.
+// a curved line is split into equal steps that each turn half before and half after moving,
+// so the heading at the end is turned by the whole bend
+fn advance(state: &mut State, length: f32, bend: f32, subdivisions: u32) -> Vec<Vec3> {
+    let steps = if bend == 0.0 { 1 } else { subdivisions.max(1) };
+    let step = Vec3::new(0.0, length * state.scale / steps as f32, 0.0);
+    let half_turn = Qua::from_axis_angle(Vec3::X, bend / steps as f32 * 0.5);
+
+    (0..steps)
+        .map(|_| {
+            state.rotation = (state.rotation * half_turn).normalize();
+            state.position += state.rotation.mul_vec3(step);
+            state.rotation = (state.rotation * half_turn).normalize();
+            state.position
+        })
+        .collect()
 }
 
 fn get_shape(
@@ -154,42 +847,57 @@ fn get_shape(
     state: &mut State,
 ) -> Option<RenderShape> {
     if let Some(shape) = render_config.shapes.get(id) {
+        let lut = render_config.lut_index(shape.lut());
+        let material = shape.material();
         let shape = match shape {
-            Shape::Line { width, length } => {
-                let end = state.position
-                    + state
-                        .rotation
-                        .mul_vec3(Vec3::new(0.0, *length * state.scale, 0.0));
+            Shape::Line {
+                width,
+                length,
+                curvature,
+                subdivisions,
+                ..
+            }
+            | Shape::Branch {
+                width,
+                length,
+                curvature,
+                subdivisions,
+                ..
+            } => {
                 let start = state.position;
-                state.position = end;
+                let bend = render_config.angle(*curvature);
+                let mut curve = advance(state, *length, bend, *subdivisions);
+                let end = curve.pop().unwrap_or(start);
+                if let Some(direction) = render_config.tropism_direction {
+                    tropism(state, Vec3::from(direction), render_config.elasticity);
+                }
                 RenderShape::Line {
                     start,
                     end,
-                    width: *width,
+                    curve,
+                    start_width: *width * state.width,
+                    end_width: *width * state.width,
                     age,
                     last_age: state.age,
+                    color: state.color,
+                    lut,
+                    material,
+                    parent: state.parent,
+                    cap: false,
+                    flat: render_config.flat_shading,
                 }
             }
-            Shape::Circle { size } => RenderShape::Circle {
+            Shape::Circle { size, .. } => RenderShape::Circle {
                 size: *size * state.scale,
                 pos: state.position,
                 age,
+                last_age: state.age,
+                heading: state.rotation.mul_vec3(Vec3::Y),
+                subdivisions: render_config.sphere_subdivisions,
+                color: state.color,
+                lut,
+                material,
             },
-            Shape::Branch { width, length } => {
-                let end = state.position
-                    + state
-                        .rotation
-                        .mul_vec3(Vec3::new(0.0, *length * state.scale, 0.0));
-                let start = state.position;
-                state.position = end;
-                RenderShape::Line {
-                    start,
-                    end,
-                    width: *width,
-                    age,
-                    last_age: state.age,
-                }
-            }
         };
         Some(shape)
     } else {
@@ -3,16 +3,23 @@ use jandering_engine::core::{engine::EngineBuilder, window::WindowBuilder};
 
 mod application;
 mod camera_controller;
-mod color_obj;
+pub mod color_obj;
 mod cylinder;
+mod grass_blade;
 mod icosphere;
 mod image;
-mod l_system;
+pub mod l_system;
+pub mod mesh;
+mod plane;
 mod render_data;
 mod timer;
+mod weld;
 
 use wasm_bindgen::prelude::*;
 
+// the generator needs no renderer, configs go in and shapes come out
+pub use l_system::{build, build_checked, config::LConfig, RenderConfig, RenderShape};
+
 #[wasm_bindgen(start)]
 async fn main() {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
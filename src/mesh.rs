@@ -0,0 +1,319 @@
+use jandering_engine::types::{Mat4, Qua, Vec2, Vec3};
+
+use crate::{color_obj::AgeVertex, cylinder, icosphere, l_system::RenderShape, weld};
+
+const CYLINDER_RESOLUTION: u32 = 3;
+
+lazy_static::lazy_static! {
+    static ref CYLINDER_DATA: (Vec<AgeVertex>, Vec<u32>) = cylinder::generate(CYLINDER_RESOLUTION);
+}
+
+// writes straight into the plant's buffers, the shared ring is only ever read.
+// the ring alternates bottom and top vertices, each gets its own radius
+fn cylinder(
+    (start, end): (Vec3, Vec3),
+    radii: (f32, f32),
+    age: f32,
+    next_age: f32,
+    color: Option<Vec3>,
+    vertices: &mut Vec<AgeVertex>,
+    indices: &mut Vec<u32>,
+) {
+    let (base_vertices, base_indices) = &*CYLINDER_DATA;
+    let index_offset = vertices.len() as u32;
+    let diff = end - start;
+    let length = diff.length();
+    let rotation = Qua::from_rotation_arc(Vec3::Y, diff.normalize());
+    let center = start + diff * 0.5;
+    // a narrowing side leans back towards the base, a plain tube keeps the ring's normals
+    let slope = if length > 0.0 {
+        (radii.0 - radii.1) / length
+    } else {
+        0.0
+    };
+    vertices.extend(base_vertices.iter().enumerate().map(|(i, e)| {
+        let (color, color_weight) = color.map_or((e.color, e.color_weight), |color| (color, 1.0));
+        let radius = if i % 2 == 0 { radii.0 } else { radii.1 };
+        let position = e.position * Vec3::new(radius, length, radius);
+        AgeVertex {
+            position: center + rotation.mul_vec3(position),
+            normal: rotation
+                .mul_vec3(e.normal + Vec3::Y * slope)
+                .normalize_or_zero(),
+            age: if i % 2 == 0 { age } else { next_age },
+            color,
+            color_weight,
+            ..*e
+        }
+    }));
+    indices.extend(base_indices.iter().map(|e| e + index_offset));
+}
+
+// a fan from the end point to the tube's last ring, with vertices of its own so the flat
+// normal doesn't bend the tube's shading
+fn cap(
+    center: Vec3,
+    ring: &[Vec3],
+    normal: Vec3,
+    age: f32,
+    color: Option<Vec3>,
+    vertices: &mut Vec<AgeVertex>,
+    indices: &mut Vec<u32>,
+) {
+    let index_offset = vertices.len() as u32;
+    vertices.extend(
+        std::iter::once(center)
+            .chain(ring.iter().copied())
+            .map(|position| AgeVertex {
+                position,
+                normal,
+                age,
+                color: color.unwrap_or_default(),
+                color_weight: if color.is_some() { 1.0 } else { 0.0 },
+                ..Default::default()
+            }),
+    );
+    let count = ring.len() as u32;
+    indices.extend(
+        (0..count)
+            .flat_map(|i| [0, 1 + i, 1 + (i + 1) % count])
+            .map(|e| e + index_offset),
+    );
+}
+
+// splits the triangles written since `first_vertex` and `first_index` apart so each gets its
+// own face normal. there's no culling so the winding says nothing about which way is out,
+// the smooth normals pick the side
+fn flatten(
+    vertices: &mut Vec<AgeVertex>,
+    indices: &mut Vec<u32>,
+    first_vertex: usize,
+    first_index: usize,
+) {
+    let corners = indices
+        .drain(first_index..)
+        .map(|e| vertices[e as usize])
+        .collect::<Vec<_>>();
+    vertices.truncate(first_vertex);
+
+    for triangle in corners.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+        let normal = (b.position - a.position)
+            .cross(c.position - a.position)
+            .normalize_or_zero();
+        let outward = a.normal + b.normal + c.normal;
+        let normal = if normal.dot(outward) < 0.0 {
+            -normal
+        } else {
+            normal
+        };
+
+        indices.extend((0..3).map(|i| (vertices.len() + i) as u32));
+        vertices.extend(triangle.iter().map(|e| AgeVertex { normal, ..*e }));
+    }
+}
+
+// one ring per point, neighbouring segments share their ring so the bend stays smooth
+fn curved_cylinder(
+    points: &[Vec3],
+    radii: (f32, f32),
+    age: f32,
+    next_age: f32,
+    color: Option<Vec3>,
+    index_offset: u32,
+) -> (Vec<AgeVertex>, Vec<u32>) {
+    let last = points.len() - 1;
+    let length = points.windows(2).map(|e| e[0].distance(e[1])).sum::<f32>();
+    let slope = if length > 0.0 {
+        (radii.0 - radii.1) / length
+    } else {
+        0.0
+    };
+    let vertices = points
+        .iter()
+        .enumerate()
+        .flat_map(|(i, point)| {
+            let tangent = points[(i + 1).min(last)] - points[i.saturating_sub(1)];
+            let rotation = Qua::from_rotation_arc(Vec3::Y, tangent.normalize());
+            let t = i as f32 / last as f32;
+            let age = age + (next_age - age) * t;
+            let radius = radii.0 + (radii.1 - radii.0) * t;
+            // the first column is repeated at the end for the uv seam, like the shared ring
+            (0..=CYLINDER_RESOLUTION).map(move |j| {
+                let u = j as f32 / CYLINDER_RESOLUTION as f32;
+                let angle = u * std::f32::consts::TAU;
+                let outward = rotation.mul_vec3(Vec3::new(angle.cos(), 0.0, angle.sin()));
+                AgeVertex {
+                    position: *point + outward * radius,
+                    normal: rotation
+                        .mul_vec3(Vec3::new(angle.cos(), slope, angle.sin()))
+                        .normalize_or_zero(),
+                    age,
+                    color: color.unwrap_or_default(),
+                    color_weight: if color.is_some() { 1.0 } else { 0.0 },
+                    // the points are evenly spaced along the curve
+                    uv: Vec2::new(u, t),
+                    ..Default::default()
+                }
+            })
+        })
+        .collect();
+
+    let columns = CYLINDER_RESOLUTION + 1;
+    let indices = (0..last as u32)
+        .flat_map(|ring| {
+            (0..CYLINDER_RESOLUTION).flat_map(move |j| {
+                let i = ring * columns;
+                let (a, b) = (i + j, i + j + 1);
+                let (c, d) = (a + columns, b + columns);
+                [a, c, b, c, b, d]
+            })
+        })
+        .map(|e| e + index_offset)
+        .collect();
+
+    (vertices, indices)
+}
+
+// turns generated shapes into one plant mesh, base_height is measured from the lowest vertex
+pub fn build(shapes: Vec<RenderShape>, should_weld: bool) -> (Vec<AgeVertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for shape in shapes {
+        shape_to_mesh_data(shape, &mut vertices, &mut indices);
+    }
+
+    let base = vertices
+        .iter()
+        .map(|e| e.position.y)
+        .fold(f32::INFINITY, f32::min);
+    vertices
+        .iter_mut()
+        .for_each(|e| e.base_height = e.position.y - base);
+
+    if should_weld {
+        let before = vertices.len();
+        (vertices, indices) = weld::weld(&vertices, &indices);
+        log::info!("welded {before} vertices into {}", vertices.len());
+    }
+
+    (vertices, indices)
+}
+
+// appends the shape to the plant's buffers
+fn shape_to_mesh_data(shape: RenderShape, vertices: &mut Vec<AgeVertex>, indices: &mut Vec<u32>) {
+    let first_vertex = vertices.len();
+    let first_index = indices.len();
+    let vertices_len = first_vertex as u32;
+    let (lut, material, flat) = match shape {
+        RenderShape::Line {
+            start,
+            end,
+            curve,
+            start_width,
+            end_width,
+            age,
+            last_age,
+            color,
+            lut,
+            material,
+            cap: capped,
+            flat,
+            ..
+        } if !curve.is_empty() => {
+            let points = std::iter::once(start)
+                .chain(curve)
+                .chain(std::iter::once(end))
+                .collect::<Vec<_>>();
+            let length = points.windows(2).map(|e| e[0].distance(e[1])).sum::<f32>();
+            let radii = (start_width * length * 0.01, end_width * length * 0.01);
+            let (new_vertices, new_indices) =
+                curved_cylinder(&points, radii, last_age, age, color, vertices_len);
+            vertices.extend(new_vertices);
+            indices.extend(new_indices);
+            if capped {
+                let ring = vertices[vertices.len() - CYLINDER_RESOLUTION as usize - 1..]
+                    .iter()
+                    .take(CYLINDER_RESOLUTION as usize)
+                    .map(|e| e.position)
+                    .collect::<Vec<_>>();
+                let heading = (end - points[points.len() - 2]).normalize_or_zero();
+                cap(end, &ring, heading, age, color, vertices, indices);
+            }
+            (lut, material, flat)
+        }
+        RenderShape::Line {
+            start,
+            end,
+            start_width,
+            end_width,
+            age,
+            last_age,
+            color,
+            lut,
+            material,
+            cap: capped,
+            flat,
+            ..
+        } => {
+            let diff = end - start;
+            let length = diff.length();
+            let radii = (start_width * length * 0.01, end_width * length * 0.01);
+            cylinder((start, end), radii, last_age, age, color, vertices, indices);
+            if capped {
+                // the top of the ring that was just written, without the repeated seam column
+                let ring = vertices[first_vertex..]
+                    .iter()
+                    .skip(1)
+                    .step_by(2)
+                    .take(CYLINDER_RESOLUTION as usize)
+                    .map(|e| e.position)
+                    .collect::<Vec<_>>();
+                cap(
+                    end,
+                    &ring,
+                    diff.normalize_or_zero(),
+                    age,
+                    color,
+                    vertices,
+                    indices,
+                );
+            }
+            (lut, material, flat)
+        }
+        RenderShape::Circle {
+            size,
+            pos,
+            age,
+            last_age,
+            heading,
+            subdivisions,
+            color,
+            lut,
+            material,
+        } => {
+            let mat = Mat4::from_scale_rotation_translation(Vec3::splat(size), Qua::default(), pos);
+            let (new_vertices, new_indices) = icosphere::generate(
+                last_age,
+                age,
+                heading,
+                subdivisions,
+                color,
+                mat,
+                vertices_len,
+            );
+            vertices.extend(new_vertices);
+            indices.extend(new_indices);
+            (lut, material, false)
+        }
+    };
+    if flat {
+        flatten(vertices, indices, first_vertex, first_index);
+    }
+    vertices[first_vertex..].iter_mut().for_each(|e| {
+        e.lut = lut as f32;
+        e.metallic = material.metallic;
+        e.roughness = material.roughness;
+    });
+}
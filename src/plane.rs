@@ -0,0 +1,49 @@
+use jandering_engine::types::Vec3;
+
+use crate::{color_obj::AgeVertex, image::Image};
+
+// everything standing on the ground goes through this so it lines up with the floor mesh
+pub fn height_at(image: &Image, height: f32, x: f32, z: f32) -> f32 {
+    image.sample(x, z) * height
+}
+
+// a size x size grid centered on the origin in world space, lifted by the heightmap
+pub fn displaced_plane(
+    resolution: u32,
+    size: f32,
+    image: &Image,
+    height: f32,
+) -> (Vec<AgeVertex>, Vec<u32>) {
+    let resolution = resolution.max(1);
+    let step = size / resolution as f32;
+    let half = size / 2.0;
+
+    let sample = |x: f32, z: f32| height_at(image, height, x, z);
+
+    let vertices = (0..=resolution)
+        .flat_map(|i| (0..=resolution).map(move |j| (j, i)))
+        .map(|(j, i)| {
+            let x = -half + j as f32 * step;
+            let z = -half + i as f32 * step;
+
+            // central differences, one grid step to each side
+            let dx = sample(x + step, z) - sample(x - step, z);
+            let dz = sample(x, z + step) - sample(x, z - step);
+            let normal = Vec3::new(-dx, 2.0 * step, -dz).normalize();
+
+            AgeVertex {
+                position: Vec3::new(x, sample(x, z), z),
+                normal,
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let row = resolution + 1;
+    let indices = (0..resolution)
+        .flat_map(|i| (0..resolution).map(move |j| i * row + j))
+        .flat_map(|e| [e, e + row, e + 1, e + 1, e + row, e + row + 1])
+        .collect();
+
+    (vertices, indices)
+}
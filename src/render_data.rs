@@ -1,6 +1,9 @@
-use jandering_engine::core::{
-    bind_group::{BindGroup, BindGroupLayout, BindGroupLayoutEntry},
-    renderer::{BufferHandle, Renderer},
+use jandering_engine::{
+    core::{
+        bind_group::{BindGroup, BindGroupLayout, BindGroupLayoutEntry},
+        renderer::{BufferHandle, Renderer},
+    },
+    types::Vec3,
 };
 
 #[repr(C)]
@@ -14,7 +17,37 @@ pub struct RenderDataData {
     pub wind_direction: f32,
     pub wind_noise_scale: f32,
     pub wind_noise_strength: f32,
-    padding: [f32; 1],
+    pub growth: f32,
+    pub emissive_intensity: f32,
+    pub tone_mapping: f32,
+    pub exposure: f32,
+    pub fog_density: f32,
+    pub fog_color: Vec3,
+    padding: f32,
+    pub sky_horizon: Vec3,
+    padding_1: f32,
+    pub sky_zenith: Vec3,
+    padding_2: f32,
+    pub light_direction: Vec3,
+    pub light_intensity: f32,
+    pub light_color: Vec3,
+    pub light_enabled: f32,
+    pub ambient: f32,
+    pub ao_strength: f32,
+    pub ao_radius: f32,
+    pub dust_size: f32,
+    pub outline_color: Vec3,
+    pub outline_width: f32,
+    pub gust_x: f32,
+    pub gust_z: f32,
+    pub wind_phase: f32,
+    padding_4: f32,
+    pub water_color: Vec3,
+    pub water_ripple: f32,
+    pub wind_field_scale: f32,
+    pub wind_field_speed: f32,
+    pub wind_field_strength: f32,
+    padding_5: f32,
 }
 
 pub struct RenderDataBindGroup {
@@ -45,7 +78,37 @@ impl RenderDataBindGroup {
             wind_direction: 0.0,
             wind_noise_scale: 0.05,
             wind_noise_strength: 5.0,
-            padding: [0.0; 1],
+            growth: 1.0,
+            emissive_intensity: 0.0,
+            tone_mapping: 0.0,
+            exposure: 1.0,
+            fog_density: 0.0,
+            fog_color: Vec3::new(0.2, 0.5, 1.0),
+            padding: 0.0,
+            sky_horizon: Vec3::new(0.2, 0.5, 1.0),
+            padding_1: 0.0,
+            sky_zenith: Vec3::new(0.2, 0.5, 1.0),
+            padding_2: 0.0,
+            light_direction: Vec3::splat(-1.0),
+            light_intensity: 1.0,
+            light_color: Vec3::ONE,
+            light_enabled: 0.0,
+            ambient: 0.5,
+            ao_strength: 0.0,
+            ao_radius: 0.05,
+            dust_size: 0.0085,
+            outline_color: Vec3::splat(0.05),
+            outline_width: 0.0,
+            gust_x: 0.0,
+            gust_z: 0.0,
+            wind_phase: 0.0,
+            padding_4: 0.0,
+            water_color: Vec3::new(0.1, 0.3, 0.35),
+            water_ripple: 0.3,
+            wind_field_scale: 0.05,
+            wind_field_speed: 0.02,
+            wind_field_strength: 0.0,
+            padding_5: 0.0,
         };
 
         let buffer_handle = renderer.create_uniform_buffer(bytemuck::cast_slice(&[data]));
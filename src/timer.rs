@@ -8,6 +8,10 @@ impl Timer {
         Self(name, Instant::now())
     }
 
+    pub fn elapsed_ms(&self) -> f32 {
+        (Instant::now() - self.1).as_secs_f32() * 1000.0
+    }
+
     pub fn print(self) {
         log::info!("{}: {}ms", self.0, (Instant::now() - self.1).as_millis());
     }
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::color_obj::AgeVertex;
+
+const POSITION_EPSILON: f32 = 1e-4;
+const NORMAL_EPSILON: f32 = 1e-3;
+const AGE_EPSILON: f32 = 1e-4;
+
+type Key = ([i32; 7], [u32; 9]);
+
+// merges vertices that only differ by float noise, anything with a different normal stays a seam
+pub fn weld(vertices: &[AgeVertex], indices: &[u32]) -> (Vec<AgeVertex>, Vec<u32>) {
+    let mut lookup = HashMap::<Key, u32>::with_capacity(vertices.len());
+    let mut welded = Vec::with_capacity(vertices.len());
+    let remap = vertices
+        .iter()
+        .map(|vertex| {
+            *lookup.entry(key(vertex)).or_insert_with(|| {
+                welded.push(*vertex);
+                welded.len() as u32 - 1
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let indices = indices.iter().map(|e| remap[*e as usize]).collect();
+    (welded, indices)
+}
+
+fn key(vertex: &AgeVertex) -> Key {
+    let quantize = |value: f32, epsilon: f32| (value / epsilon).round() as i32;
+    (
+        [
+            quantize(vertex.position.x, POSITION_EPSILON),
+            quantize(vertex.position.y, POSITION_EPSILON),
+            quantize(vertex.position.z, POSITION_EPSILON),
+            quantize(vertex.normal.x, NORMAL_EPSILON),
+            quantize(vertex.normal.y, NORMAL_EPSILON),
+            quantize(vertex.normal.z, NORMAL_EPSILON),
+            quantize(vertex.age, AGE_EPSILON),
+        ],
+        [
+            vertex.color.x.to_bits(),
+            vertex.color.y.to_bits(),
+            vertex.color.z.to_bits(),
+            vertex.color_weight.to_bits(),
+            vertex.lut.to_bits(),
+            vertex.metallic.to_bits(),
+            vertex.roughness.to_bits(),
+            // the seam of a tube has the same position on both sides but not the same uv
+            vertex.uv.x.to_bits(),
+            vertex.uv.y.to_bits(),
+        ],
+    )
+}
@@ -0,0 +1,31 @@
+mod common;
+
+// the same system with and without notes has to parse to the same symbols
+fn parse(initial: &str, result: &str) -> String {
+    let config = common::single_rule(initial, result).unwrap();
+    format!("{:?}", config.rules)
+}
+
+#[test]
+fn comment_at_the_start_is_skipped() {
+    assert_eq!(
+        parse("# the trunk\nfA", "# grows a branch\nf[+A]"),
+        parse("fA", "f[+A]")
+    );
+}
+
+#[test]
+fn comment_in_the_middle_runs_to_the_end_of_the_line() {
+    assert_eq!(
+        parse("f # trunk\nA", "f[+(20~40#5) # turn\nA]"),
+        parse("f A", "f[+(20~40#5) A]")
+    );
+}
+
+#[test]
+fn comment_at_the_end_is_skipped() {
+    assert_eq!(
+        parse("fA # done", "f[+A]# last branch [-A]"),
+        parse("fA", "f[+A]")
+    );
+}
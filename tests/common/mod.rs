@@ -0,0 +1,26 @@
+// small inline systems for tests that only care about a few symbols. every one has a single
+// line shape `f` of unit width and length, and angles in degrees
+#![allow(dead_code)]
+
+use l_system::LConfig;
+
+// `rules` is the body of the rules object, keyed by rule id
+pub fn system(initial: &str, rules: &str) -> Result<LConfig, String> {
+    LConfig::from_json(format!(
+        r#"{{
+            "rendering": {{
+                "default_angle_change": 30.0,
+                "shapes": {{ "f": {{ "Line": {{ "width": 1.0, "length": 1.0 }} }} }}
+            }},
+            "rules": {{ "iterations": 3, "initial": {initial:?}, "rules": {{ {rules} }} }}
+        }}"#
+    ))
+}
+
+// the only rule is `A`, with a single production
+pub fn single_rule(initial: &str, result: &str) -> Result<LConfig, String> {
+    system(
+        initial,
+        &format!(r#""A": [{{ "rules": [{{ "result": {result:?} }}] }}]"#),
+    )
+}
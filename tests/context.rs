@@ -0,0 +1,68 @@
+mod common;
+
+use l_system::build;
+use rand::{rngs::StdRng, SeedableRng};
+
+// `A` draws a line only between a `B` and a `C`, `B` and `C` draw nothing and `D` turns into an `A`
+fn line_count(initial: &str) -> usize {
+    let config = common::system(
+        initial,
+        r#"
+            "A": [{ "rules": [{ "result": "B < A > C = f" }] }],
+            "B": [{ "rules": [{ "result": "" }] }],
+            "C": [{ "rules": [{ "result": "" }] }],
+            "D": [{ "rules": [{ "result": "A" }] }]
+        "#,
+    )
+    .unwrap();
+    build(&config, &mut StdRng::seed_from_u64(0)).len()
+}
+
+#[test]
+fn matching_context_applies() {
+    assert_eq!(line_count("BAC"), 1);
+}
+
+#[test]
+fn other_context_does_not_apply() {
+    assert_eq!(line_count("CAB"), 0);
+    assert_eq!(line_count("BA"), 0);
+}
+
+#[test]
+fn branches_are_skipped() {
+    assert_eq!(line_count("B[f]A[C]C"), 2);
+}
+
+#[test]
+fn branch_ends_stop_the_right_side() {
+    assert_eq!(line_count("B[A]C"), 0);
+}
+
+// `D` expands to a lone `A`. the `B` and `C` around the `D` are rewritten in the same step, so
+// they're not next to the `A` in the string that step produces
+#[test]
+fn rules_past_production_edges_are_not_context() {
+    assert_eq!(line_count("BDC"), 0);
+    assert_eq!(line_count("CDB"), 0);
+}
+
+// objects stay put when the string is rewritten, so they are still next to the `A`
+#[test]
+fn objects_past_production_edges_are_context() {
+    let config = common::system(
+        "fDf",
+        r#"
+            "A": [{ "rules": [{ "result": "f < A > f = ff" }] }],
+            "D": [{ "rules": [{ "result": "A" }] }]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(build(&config, &mut StdRng::seed_from_u64(0)).len(), 4);
+}
+
+#[test]
+fn predecessor_has_to_be_the_rule() {
+    let config = common::single_rule("A", "B < C > D = f");
+    assert!(config.is_err_and(|e| e.contains("'A'")));
+}
@@ -0,0 +1,108 @@
+use jandering_engine::types::Vec3;
+use l_system::{build, mesh, LConfig, RenderShape};
+use rand::{rngs::StdRng, SeedableRng};
+
+const SEED: u64 = 42;
+const ITERATIONS: u32 = 5;
+
+fn initial_config() -> LConfig {
+    let text = include_str!("../res/systems/initial.json").to_string();
+    let mut config = LConfig::from_json(text).unwrap();
+    config.rules.iterations = ITERATIONS;
+    config
+}
+
+fn generate(seed: u64) -> Vec<RenderShape> {
+    grow(initial_config(), seed)
+}
+
+// same steps as Application::new_plant, without the renderer
+fn grow(mut config: LConfig, seed: u64) -> Vec<RenderShape> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    config.randomize_rule_sets(None, &mut rng);
+    build(&config, &mut rng)
+}
+
+fn counts(shapes: &[RenderShape]) -> (usize, usize) {
+    let lines = shapes
+        .iter()
+        .filter(|e| matches!(e, RenderShape::Line { .. }))
+        .count();
+    (lines, shapes.len() - lines)
+}
+
+fn extents(shapes: &[RenderShape]) -> (Vec3, Vec3) {
+    shapes
+        .iter()
+        .flat_map(|shape| match shape {
+            RenderShape::Line {
+                start, end, curve, ..
+            } => [*start, *end].into_iter().chain(curve.clone()).collect(),
+            RenderShape::Circle { pos, .. } => vec![*pos],
+        })
+        .fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), e| (min.min(e), max.max(e)),
+        )
+}
+
+#[test]
+fn same_seed_grows_the_same_plant() {
+    let first = generate(SEED);
+    let second = generate(SEED);
+
+    assert_eq!(counts(&first), counts(&second));
+    let (first_min, first_max) = extents(&first);
+    let (second_min, second_max) = extents(&second);
+    assert!(first_min.abs_diff_eq(second_min, 1e-5));
+    assert!(first_max.abs_diff_eq(second_max, 1e-5));
+}
+
+#[test]
+fn earlier_rule_set_picks_dont_change_the_plant() {
+    let mut config = initial_config();
+    config.randomize_rule_sets(Some(1), &mut StdRng::seed_from_u64(SEED + 1));
+    let reseeded = grow(config, SEED);
+    let fresh = generate(SEED);
+
+    assert_eq!(counts(&reseeded), counts(&fresh));
+    let (reseeded_min, reseeded_max) = extents(&reseeded);
+    let (fresh_min, fresh_max) = extents(&fresh);
+    assert!(reseeded_min.abs_diff_eq(fresh_min, 1e-5));
+    assert!(reseeded_max.abs_diff_eq(fresh_max, 1e-5));
+}
+
+#[test]
+fn initial_config_grows_from_the_origin() {
+    let shapes = generate(SEED);
+    let (lines, _) = counts(&shapes);
+    assert!(lines > 0);
+
+    // the turtle starts at the origin, so every bound has to reach it
+    let (min, max) = extents(&shapes);
+    assert!(min.is_finite() && max.is_finite());
+    assert!(
+        min.cmple(Vec3::splat(1e-5)).all(),
+        "min {min} doesn't reach the origin"
+    );
+    assert!(
+        max.cmpge(Vec3::splat(-1e-5)).all(),
+        "max {max} doesn't reach the origin"
+    );
+    assert!(max.y > 0.0);
+}
+
+#[test]
+fn mesh_covers_every_shape() {
+    let (vertices, indices) = mesh::build(generate(SEED), false);
+
+    assert!(!vertices.is_empty());
+    assert_eq!(indices.len() % 3, 0);
+    assert!(indices.iter().all(|e| (*e as usize) < vertices.len()));
+
+    let base = vertices
+        .iter()
+        .map(|e| e.base_height)
+        .fold(f32::INFINITY, f32::min);
+    assert!(base.abs() < 1e-5);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use jandering_engine::types::Vec3;
+use l_system::{build, build_checked, RenderShape};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn only_line(shapes: &[RenderShape]) -> Vec3 {
+    let [RenderShape::Line { start, end, .. }] = shapes else {
+        panic!("expected a single line, got {shapes:?}");
+    };
+    *end - *start
+}
+
+// a thousand small pitches add up to a quarter turn from up to +z, a drifting rotation would
+// skew the heading and stretch or shrink the line
+#[test]
+fn long_rotation_chain_stays_within_tolerance() {
+    let initial = format!("{}f", "&(0.09)".repeat(1000));
+    let config = common::system(&initial, "").unwrap();
+    let direction = only_line(&build(&config, &mut StdRng::seed_from_u64(0)));
+
+    assert!((direction.length() - 1.0).abs() < 1e-4, "{direction}");
+    assert!(
+        direction.normalize().abs_diff_eq(Vec3::Z, 1e-3),
+        "{direction}"
+    );
+}
+
+// a range with nothing between its bounds is just that value, it used to panic the page
+#[test]
+fn empty_range_gives_its_bound() {
+    for initial in ["_(5~5)f", "_(5~5#0)f"] {
+        let config = common::system(initial, "").unwrap();
+        let shapes = build(&config, &mut StdRng::seed_from_u64(0));
+        let [RenderShape::Line { start, .. }] = &shapes[..] else {
+            panic!("expected a single line, got {shapes:?}");
+        };
+        assert_eq!(*start, Vec3::new(0.0, 5.0, 0.0), "{initial}");
+    }
+}
+
+// lines built and whether the budget cut the plant short
+fn budgeted(initial: &str, max_symbols: u32) -> (usize, bool) {
+    let mut config = common::single_rule(initial, "f").unwrap();
+    config.rules.max_symbols = max_symbols;
+    let (shapes, cut) = build_checked(&config, &mut StdRng::seed_from_u64(0));
+    (shapes.len(), cut)
+}
+
+fn line_count(initial: &str, max_symbols: u32) -> usize {
+    budgeted(initial, max_symbols).0
+}
+
+#[test]
+fn symbol_budget_stops_the_build() {
+    assert_eq!(budgeted("ffffffffff", 4), (4, true));
+}
+
+// walking exactly as many symbols as the budget allows drops nothing
+#[test]
+fn using_up_the_budget_is_not_a_cut() {
+    assert_eq!(budgeted("ffff", 4), (4, false));
+    assert_eq!(budgeted("fffff", 4), (4, true));
+    assert_eq!(budgeted("AA", 4), (2, false));
+}
+
+#[test]
+fn repeat_count_is_clamped() {
+    assert_eq!(line_count("*(4000000000)A", 0), 10_000);
+}
+
+// the repeat takes 1 of the 100, then every copy takes 1 for itself and 1 for its `f`. the last
+// copy gets charged but has nothing left for the `f`, without the charge it'd be 99 lines
+#[test]
+fn repeat_copies_are_charged_to_the_symbol_budget() {
+    assert_eq!(line_count("*(4000000000)A", 100), 49);
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use common::{single_rule, system};
+
+#[test]
+fn defined_references_load() {
+    assert!(single_rule("fA", "f[+A]").is_ok());
+}
+
+#[test]
+fn undefined_references_are_errors() {
+    let error = single_rule("fAg", "f[+B]").err().unwrap();
+    assert!(error.contains("undefined rule 'B'"), "{error}");
+    assert!(error.contains("undefined shape 'g'"), "{error}");
+}
+
+#[test]
+fn empty_rule_sets_are_errors() {
+    let error = system("fA", r#""A": [{ "rules": [] }]"#).err().unwrap();
+    assert!(error.contains("rule set 1 of 'A' has no rules"), "{error}");
+}